@@ -0,0 +1,169 @@
+/// Console file backing stdin, stdout and stderr
+///
+/// Output goes straight to the arceos console; input is polled from it.
+/// The console is also the only object that answers the TTY ioctls.
+
+use alloc::collections::VecDeque;
+use core::{any::Any, time::Duration};
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::{DeviceId, Metadata, NodePermission, NodeType};
+use axpoll::IoEvents;
+use super::{
+    fd_table::{SpinLock, FD_NONBLOCK},
+    file::FileLike,
+    poll,
+};
+
+/// ioctl command constants
+const TCGETS: u32 = 0x5401;       // Get terminal attributes
+const TCSETS: u32 = 0x5402;       // Set terminal attributes
+const TCSETSW: u32 = 0x5403;      // Set terminal attributes after draining output
+const TCSETSF: u32 = 0x5404;      // Set terminal attributes after flushing input
+const TIOCGPGRP: u32 = 0x540f;    // Get foreground process group
+const TIOCSPGRP: u32 = 0x5410;    // Set foreground process group
+const TIOCGWINSZ: u32 = 0x5413;   // Get window size
+const TIOCSWINSZ: u32 = 0x5414;   // Set window size
+
+/// Terminal attributes structure (simplified)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Termios {
+    pub c_iflag: u32,
+    pub c_oflag: u32,
+    pub c_cflag: u32,
+    pub c_lflag: u32,
+    pub c_line: u8,
+    pub c_cc: [u8; 32],
+}
+
+/// Window size structure
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Winsize {
+    pub ws_row: u16,
+    pub ws_col: u16,
+    pub ws_xpixel: u16,
+    pub ws_ypixel: u16,
+}
+
+/// Input taken from the console by poll but not yet read
+static PENDING_INPUT: SpinLock<VecDeque<u8>> = SpinLock::new(VecDeque::new());
+
+/// The system console as a character device (/dev/console, 5:1)
+pub struct Console;
+
+impl Console {
+    /// Take buffered input first, then whatever the console has
+    fn read_input(buf: &mut [u8]) -> usize {
+        let mut pending = PENDING_INPUT.lock();
+        let n = buf.len().min(pending.len());
+        for (dst, src) in buf.iter_mut().zip(pending.drain(..n)) {
+            *dst = src;
+        }
+        drop(pending);
+        n + axhal::console::read_bytes(&mut buf[n..])
+    }
+}
+
+impl FileLike for Console {
+    /// Read at least one byte of console input.
+    /// Blocks until input arrives unless the description is nonblocking.
+    fn read(&self, buf: &mut [u8], flags: u32) -> Result<usize, LinuxError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if flags & FD_NONBLOCK != 0 {
+            return match Self::read_input(buf) {
+                0 => Err(LinuxError::EAGAIN),
+                n => Ok(n),
+            };
+        }
+        // The console raises no wakeups, so sleep between rescans like poll does
+        poll::wait_ready(None, |_| Ok(Self::read_input(buf)))
+    }
+
+    /// Write everything in `buf` to the console
+    fn write(&self, buf: &[u8], _flags: u32) -> Result<usize, LinuxError> {
+        axhal::console::write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    /// Metadata reported by fstat on a console fd
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        Ok(Metadata {
+            device: 0,
+            inode: 0,
+            nlink: 1,
+            mode: NodePermission::from_bits_truncate(0o620),
+            node_type: NodeType::CharacterDevice,
+            uid: 0,
+            gid: 0,
+            size: 0,
+            block_size: 1024,
+            blocks: 0,
+            rdev: DeviceId::new(5, 1),
+            atime: Duration::ZERO,
+            mtime: Duration::ZERO,
+            ctime: Duration::ZERO,
+        })
+    }
+
+    /// Input is ready once a byte has arrived; it is kept for the next read
+    fn poll(&self) -> IoEvents {
+        let mut pending = PENDING_INPUT.lock();
+        if pending.is_empty() {
+            let mut byte = [0u8; 1];
+            if axhal::console::read_bytes(&mut byte) > 0 {
+                pending.push_back(byte[0]);
+            }
+        }
+        if pending.is_empty() {
+            IoEvents::OUT
+        } else {
+            IoEvents::IN | IoEvents::OUT
+        }
+    }
+
+    /// Handle the terminal ioctls.
+    /// Attributes and window size are fixed; set requests are accepted and ignored.
+    fn ioctl(&self, cmd: u32, arg: usize) -> Result<isize, LinuxError> {
+        match cmd {
+            TCGETS => {
+                let termios = Termios {
+                    c_iflag: 0x0300,
+                    c_oflag: 0x0005,
+                    c_cflag: 0xbf08,
+                    c_lflag: 0x8a3b,
+                    c_line: 0,
+                    c_cc: [3, 28, 127, 21, 4, 1, 0, 0, 17, 19, 26, 0, 18, 15, 23, 22, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                };
+                unsafe { (arg as *mut Termios).write_unaligned(termios); }
+                Ok(0)
+            }
+            TCSETS | TCSETSW | TCSETSF => Ok(0),
+            TIOCGWINSZ => {
+                let winsize = Winsize {
+                    ws_row: 24,
+                    ws_col: 80,
+                    ws_xpixel: 0,
+                    ws_ypixel: 0,
+                };
+                unsafe { (arg as *mut Winsize).write_unaligned(winsize); }
+                Ok(0)
+            }
+            TIOCSWINSZ => Ok(0),
+            TIOCGPGRP => {
+                // Single-process mode: we are always the foreground group
+                unsafe { (arg as *mut i32).write_unaligned(1); }
+                Ok(0)
+            }
+            TIOCSPGRP => Ok(0),
+            _ => Err(LinuxError::ENOTTY),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
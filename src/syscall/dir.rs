@@ -0,0 +1,180 @@
+/// Directory file descriptors and getdents64
+///
+/// A directory fd keeps the resume cookie handed out by the VFS, so successive
+/// getdents64 calls continue where the previous one stopped and lseek(fd, 0,
+/// SEEK_SET) (what rewinddir does) starts the listing over.
+
+use core::any::Any;
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::{DirEntrySink, Location, Metadata, NodeType};
+use axio::SeekFrom;
+use axpoll::IoEvents;
+
+use super::{
+    ax_err_to_linux,
+    fd_table::{self, SpinLock},
+    file::FileLike,
+    linux_err_to_isize,
+};
+
+/// Size of the fixed part of `struct linux_dirent64`
+/// (d_ino: u64, d_off: i64, d_reclen: u16, d_type: u8)
+const DIRENT64_HEADER: usize = 19;
+
+/// Open directory with its iteration position
+pub struct Directory {
+    loc: Location,
+    offset: SpinLock<u64>,
+}
+
+impl Directory {
+    pub fn new(loc: Location) -> Self {
+        Self {
+            loc,
+            offset: SpinLock::new(0),
+        }
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.loc
+    }
+}
+
+impl FileLike for Directory {
+    fn read(&self, _buf: &mut [u8], _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EISDIR)
+    }
+
+    fn write(&self, _buf: &[u8], _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EISDIR)
+    }
+
+    fn read_at(&self, _buf: &mut [u8], _offset: u64) -> Result<usize, LinuxError> {
+        Err(LinuxError::EISDIR)
+    }
+
+    fn write_at(&self, _buf: &[u8], _offset: u64, _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EISDIR)
+    }
+
+    /// Reposition the directory stream (lseek on a directory fd).
+    /// Only absolute positions and queries of the current one are meaningful.
+    fn seek(&self, pos: SeekFrom) -> Result<u64, LinuxError> {
+        let mut cur = self.offset.lock();
+        let new = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(offset) => cur.checked_add_signed(offset).ok_or(LinuxError::EINVAL)?,
+            SeekFrom::End(_) => return Err(LinuxError::EINVAL),
+        };
+        if new > i64::MAX as u64 {
+            return Err(LinuxError::EINVAL);
+        }
+        *cur = new;
+        Ok(new)
+    }
+
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        self.loc.metadata().map_err(ax_err_to_linux)
+    }
+
+    fn poll(&self) -> IoEvents {
+        IoEvents::IN | IoEvents::OUT
+    }
+
+    fn mmap(&self, _buf: &mut [u8], _offset: u64) -> Result<(), LinuxError> {
+        Err(LinuxError::EISDIR)
+    }
+
+    /// Directory entries live in filesystem metadata
+    fn sync(&self, _data_only: bool) -> Result<(), LinuxError> {
+        self.loc.filesystem().flush().map_err(ax_err_to_linux)
+    }
+
+    fn location(&self) -> Option<&Location> {
+        Some(&self.loc)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Packs entries into a user buffer as `linux_dirent64` records
+struct DirentBuffer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    /// Cookie to resume from after the last packed entry
+    next_offset: u64,
+}
+
+impl DirEntrySink for DirentBuffer<'_> {
+    fn accept(&mut self, name: &str, ino: u64, node_type: NodeType, offset: u64) -> bool {
+        // Header, name and its NUL terminator, padded to 8 bytes
+        let reclen = (DIRENT64_HEADER + name.len() + 1 + 7) & !7;
+        if self.len + reclen > self.buf.len() {
+            return false;
+        }
+        
+        let rec = &mut self.buf[self.len..self.len + reclen];
+        rec[0..8].copy_from_slice(&ino.to_ne_bytes());
+        rec[8..16].copy_from_slice(&(offset as i64).to_ne_bytes());
+        rec[16..18].copy_from_slice(&(reclen as u16).to_ne_bytes());
+        rec[18] = node_type as u8;
+        rec[DIRENT64_HEADER..DIRENT64_HEADER + name.len()].copy_from_slice(name.as_bytes());
+        rec[DIRENT64_HEADER + name.len()..].fill(0);
+        
+        self.len += reclen;
+        self.next_offset = offset;
+        true
+    }
+}
+
+/// Syscall: getdents64(2) - Read directory entries
+/// Returns number of bytes written to `dirp`, 0 at end of directory,
+/// negative error on failure (EINVAL if `count` cannot hold the next entry)
+pub fn sys_getdents64(fd: i32, dirp: usize, count: usize) -> isize {
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    
+    let dir = match file.as_directory() {
+        Ok(d) => d,
+        Err(e) => return linux_err_to_isize(e),
+    };
+    
+    let mut offset = dir.offset.lock();
+    let mut sink = DirentBuffer {
+        buf: unsafe { core::slice::from_raw_parts_mut(dirp as *mut u8, count) },
+        len: 0,
+        next_offset: *offset,
+    };
+    
+    if let Err(e) = dir.loc.read_dir(*offset, &mut sink) {
+        return linux_err_to_isize(ax_err_to_linux(e));
+    }
+    
+    // Entries remain but not even one fits in the buffer
+    if sink.len == 0 && sink.next_offset == *offset && has_entry_at(&dir.loc, *offset) {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    
+    *offset = sink.next_offset;
+    sink.len as isize
+}
+
+/// Check whether the directory has another entry at `offset`
+fn has_entry_at(loc: &Location, offset: u64) -> bool {
+    struct Probe(bool);
+    
+    impl DirEntrySink for Probe {
+        fn accept(&mut self, _name: &str, _ino: u64, _node_type: NodeType, _offset: u64) -> bool {
+            self.0 = true;
+            false
+        }
+    }
+    
+    let mut probe = Probe(false);
+    loc.read_dir(offset, &mut probe).is_ok() && probe.0
+}
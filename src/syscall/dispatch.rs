@@ -1,16 +1,55 @@
-use super::{fs, mm, sync, sys, table::Sysno, task, thread, ENOSYS};
+use super::{dir, epoll, eventfd, fs, inotify, io, lock, memfd, mm, namespace, pipe, poll, signal, signalfd, sync, sys, stat, table::Sysno, task, thread, timerfd, ENOSYS};
 
 pub fn handle_syscall(nr: usize, args: [usize; 6]) -> isize {
     match Sysno::from(nr) {
         Some(Sysno::Ioctl) => sys::sys_ioctl(args[0] as i32, args[1] as u32, args[2]),
         Some(Sysno::Fcntl) => fs::sys_fcntl(args[0] as i32, args[1] as i32, args[2]),
+        Some(Sysno::Flock) => lock::sys_flock(args[0] as i32, args[1] as u32),
+        Some(Sysno::Eventfd2) => eventfd::sys_eventfd2(args[0] as u32, args[1] as u32),
+        Some(Sysno::EpollCreate1) => epoll::sys_epoll_create1(args[0] as u32),
+        Some(Sysno::EpollCtl) => epoll::sys_epoll_ctl(args[0] as i32, args[1] as u32, args[2] as i32, args[3]),
+        Some(Sysno::EpollPwait) => epoll::sys_epoll_pwait(args[0] as i32, args[1], args[2] as i32, args[3] as i32, args[4], args[5]),
+        Some(Sysno::EpollPwait2) => epoll::sys_epoll_pwait2(args[0] as i32, args[1], args[2] as i32, args[3], args[4], args[5]),
+        Some(Sysno::Dup) => fs::sys_dup(args[0] as i32),
+        Some(Sysno::Dup3) => fs::sys_dup3(args[0] as i32, args[1] as i32, args[2] as u32),
+        Some(Sysno::InotifyInit1) => inotify::sys_inotify_init1(args[0] as u32),
+        Some(Sysno::InotifyAddWatch) => inotify::sys_inotify_add_watch(args[0] as i32, args[1], args[2] as u32),
+        Some(Sysno::InotifyRmWatch) => inotify::sys_inotify_rm_watch(args[0] as i32, args[1] as i32),
         Some(Sysno::Brk) => mm::sys_brk(args[0]),
+        Some(Sysno::Mkdirat) => namespace::sys_mkdirat(args[0] as isize, args[1], args[2] as u32),
+        Some(Sysno::Unlinkat) => namespace::sys_unlinkat(args[0] as isize, args[1], args[2] as u32),
+        Some(Sysno::Symlinkat) => namespace::sys_symlinkat(args[0], args[1] as isize, args[2]),
+        Some(Sysno::Linkat) => namespace::sys_linkat(args[0] as isize, args[1], args[2] as isize, args[3], args[4] as u32),
+        Some(Sysno::Renameat2) => namespace::sys_renameat2(args[0] as isize, args[1], args[2] as isize, args[3], args[4] as u32),
         Some(Sysno::Close) => fs::sys_close(args[0] as i32),
+        Some(Sysno::Pipe2) => pipe::sys_pipe2(args[0], args[1] as u32),
+        Some(Sysno::CloseRange) => fs::sys_close_range(args[0] as u32, args[1] as u32, args[2] as u32),
+        Some(Sysno::Truncate) => fs::sys_truncate(args[0], args[1] as i64),
+        Some(Sysno::Ftruncate) => fs::sys_ftruncate(args[0] as i32, args[1] as i64),
+        Some(Sysno::Fallocate) => fs::sys_fallocate(args[0] as i32, args[1] as u32, args[2] as i64, args[3] as i64),
         Some(Sysno::OpenAt) => fs::sys_openat(args[0] as isize, args[1], args[2], args[3]),
+        Some(Sysno::Getdents64) => dir::sys_getdents64(args[0] as i32, args[1], args[2]),
         Some(Sysno::Lseek) => fs::sys_lseek(args[0] as i32, args[1] as isize, args[2] as i32),
         Some(Sysno::Read) => fs::sys_read(args[0] as i32, args[1], args[2]),
         Some(Sysno::Write) => fs::sys_write(args[0] as i32, args[1], args[2]),
-        Some(Sysno::Fstat) => fs::sys_fstat(args[0] as i32, args[1]),
+        Some(Sysno::Readv) => io::sys_readv(args[0] as i32, args[1], args[2] as i32),
+        Some(Sysno::Writev) => io::sys_writev(args[0] as i32, args[1], args[2] as i32),
+        Some(Sysno::Pread64) => io::sys_pread64(args[0] as i32, args[1], args[2], args[3] as i64),
+        Some(Sysno::Pwrite64) => io::sys_pwrite64(args[0] as i32, args[1], args[2], args[3] as i64),
+        Some(Sysno::Preadv) => io::sys_preadv(args[0] as i32, args[1], args[2] as i32, args[3] as i64),
+        Some(Sysno::Pwritev) => io::sys_pwritev(args[0] as i32, args[1], args[2] as i32, args[3] as i64),
+        Some(Sysno::Preadv2) => io::sys_preadv2(args[0] as i32, args[1], args[2] as i32, args[3] as i64, args[5] as u32),
+        Some(Sysno::Pwritev2) => io::sys_pwritev2(args[0] as i32, args[1], args[2] as i32, args[3] as i64, args[5] as u32),
+        Some(Sysno::Fstat) => stat::sys_fstat(args[0] as i32, args[1]),
+        Some(Sysno::Sync) => fs::sys_sync(),
+        Some(Sysno::Fsync) => fs::sys_fsync(args[0] as i32),
+        Some(Sysno::Fdatasync) => fs::sys_fdatasync(args[0] as i32),
+        Some(Sysno::Syncfs) => fs::sys_syncfs(args[0] as i32),
+        Some(Sysno::TimerfdCreate) => timerfd::sys_timerfd_create(args[0] as u32, args[1] as u32),
+        Some(Sysno::TimerfdSettime) => timerfd::sys_timerfd_settime(args[0] as i32, args[1] as u32, args[2], args[3]),
+        Some(Sysno::TimerfdGettime) => timerfd::sys_timerfd_gettime(args[0] as i32, args[1]),
+        Some(Sysno::Newfstatat) => stat::sys_newfstatat(args[0] as isize, args[1], args[2], args[3] as u32),
+        Some(Sysno::Statx) => stat::sys_statx(args[0] as isize, args[1], args[2] as u32, args[3] as u32, args[4]),
         Some(Sysno::Exit) => task::sys_exit(args[0] as i32),
         Some(Sysno::ExitGroup) => task::sys_exit_group(args[0] as i32),
         Some(Sysno::SetRobustList) => sync::sys_set_robust_list(args[0], args[1]),
@@ -18,8 +57,13 @@ pub fn handle_syscall(nr: usize, args: [usize; 6]) -> isize {
         Some(Sysno::Mmap) => mm::sys_mmap(args[0], args[1], args[2], args[3], args[4], args[5]),
         Some(Sysno::Mprotect) => sys::sys_mprotect(args[0], args[1], args[2] as i32),
         Some(Sysno::Prlimit64) => sys::sys_prlimit64(args[0] as i32, args[1] as u32, args[2], args[3]),
+        Some(Sysno::Pselect6) => poll::sys_pselect6(args[0] as i32, args[1], args[2], args[3], args[4], args[5]),
+        Some(Sysno::Ppoll) => poll::sys_ppoll(args[0], args[1], args[2], args[3], args[4]),
+        Some(Sysno::Signalfd4) => signalfd::sys_signalfd4(args[0] as i32, args[1], args[2], args[3] as u32),
+        Some(Sysno::RtSigprocmask) => signal::sys_rt_sigprocmask(args[0] as u32, args[1], args[2], args[3]),
         Some(Sysno::Readlinkat) => sys::sys_readlinkat(args[0] as i32, args[1], args[2], args[3]),
         Some(Sysno::Getrandom) => sys::sys_getrandom(args[0], args[1], args[2] as u32),
+        Some(Sysno::MemfdCreate) => memfd::sys_memfd_create(args[0], args[1] as u32),
         None => ENOSYS,
     }
 }
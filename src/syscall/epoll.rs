@@ -0,0 +1,526 @@
+/// epoll instances: epoll_create1, epoll_ctl, epoll_pwait, epoll_pwait2
+///
+/// An epoll fd holds an interest list keyed by (fd, open file description), as
+/// on Linux. Each interest registers its own waker with the watched object
+/// (axpoll), which marks the interest as having new events and wakes anyone
+/// waiting on the epoll instance. An interest goes away once its open file
+/// description is closed everywhere, not when one of its fds is closed.
+/// EPOLLEXCLUSIVE interests in one object share a single registration that
+/// passes each wakeup to just one of them.
+
+use alloc::{
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+    task::Wake,
+    vec::Vec,
+};
+use core::{
+    any::Any,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    task::{Context, Waker},
+    time::Duration,
+};
+
+use axerrno::LinuxError;
+use axfs::File;
+use axfs_ng_vfs::Metadata;
+use axpoll::{IoEvents, PollSet};
+use linux_raw_sys::general::{
+    epoll_event, EPOLLERR, EPOLLET, EPOLLEXCLUSIVE, EPOLLHUP, EPOLLIN, EPOLLONESHOT, EPOLLOUT,
+    EPOLLWAKEUP, EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLL_CTL_DEL, EPOLL_CTL_MOD, O_PATH, O_RDWR,
+};
+
+use super::{
+    dir::Directory,
+    fd_table::{self, OpenFile, SpinLock, FD_CLOEXEC},
+    file::{anon_inode_metadata, FileLike},
+    linux_err_to_isize,
+    poll::{self, file_events},
+    signal,
+};
+
+/// Flags in `epoll_event.events` that select behavior rather than events
+const EPOLL_FLAGS: u32 = EPOLLET | EPOLLONESHOT | EPOLLEXCLUSIVE | EPOLLWAKEUP;
+
+/// Events allowed together with EPOLLEXCLUSIVE
+const EPOLLEXCLUSIVE_OK: u32 = EPOLLIN | EPOLLOUT | EPOLLERR | EPOLLHUP | EPOLLWAKEUP | EPOLLET | EPOLLEXCLUSIVE;
+
+/// Deepest chain of epoll instances watching each other (EPOLL_MAX_NESTS)
+const MAX_NESTS: usize = 4;
+
+/// Interest list key: fd number and the open file description it referred to
+type Key = (i32, usize);
+
+/// Registered with the watched object; fires when it may have new events
+struct InterestWaker {
+    /// Registered and not yet fired
+    armed: AtomicBool,
+    /// Fired since the interest was last reported
+    dirty: AtomicBool,
+    /// Waiters of the epoll instance
+    ready: Arc<PollSet>,
+}
+
+impl Wake for InterestWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.armed.store(false, Ordering::Release);
+        self.dirty.store(true, Ordering::Release);
+        self.ready.wake();
+    }
+}
+
+/// EPOLLEXCLUSIVE interests in one watched object
+///
+/// The group registers with the object on behalf of all members and hands
+/// each wakeup to the next live member in turn, so an event wakes one epoll
+/// instance instead of all of them.
+struct ExclusiveGroup {
+    /// Registered and not yet fired
+    armed: AtomicBool,
+    members: SpinLock<Vec<Weak<InterestWaker>>>,
+    /// Round-robin position of the member to wake next
+    next: AtomicUsize,
+}
+
+/// Exclusive groups, keyed by the address of the watched object
+static EXCLUSIVE_GROUPS: SpinLock<BTreeMap<usize, Weak<ExclusiveGroup>>> =
+    SpinLock::new(BTreeMap::new());
+
+impl ExclusiveGroup {
+    /// Add `member` to the group of the object behind `file`, creating the group if needed
+    fn join(file: &OpenFile, member: &Arc<InterestWaker>) -> Arc<Self> {
+        let key = &*file.object as *const dyn FileLike as *const () as usize;
+        let mut groups = EXCLUSIVE_GROUPS.lock();
+        groups.retain(|_, group| group.strong_count() > 0);
+        let group = match groups.get(&key).and_then(Weak::upgrade) {
+            Some(group) => group,
+            None => {
+                let group = Arc::new(Self {
+                    armed: AtomicBool::new(false),
+                    members: SpinLock::new(Vec::new()),
+                    next: AtomicUsize::new(0),
+                });
+                groups.insert(key, Arc::downgrade(&group));
+                group
+            }
+        };
+        drop(groups);
+        group.members.lock().push(Arc::downgrade(member));
+        group
+    }
+
+    /// Register with the watched object unless the group is still registered
+    fn arm(self: &Arc<Self>, file: &OpenFile) {
+        if !self.armed.swap(true, Ordering::AcqRel) {
+            let waker = Waker::from(self.clone());
+            let events = IoEvents::from_bits_truncate(EPOLLIN | EPOLLOUT | EPOLLERR | EPOLLHUP);
+            file.object
+                .register(&mut Context::from_waker(&waker), events);
+        }
+    }
+}
+
+impl Wake for ExclusiveGroup {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.armed.store(false, Ordering::Release);
+        let member = {
+            let mut members = self.members.lock();
+            members.retain(|member| member.strong_count() > 0);
+            if members.is_empty() {
+                return;
+            }
+            let i = self.next.fetch_add(1, Ordering::Relaxed) % members.len();
+            members[i].upgrade()
+        };
+        if let Some(member) = member {
+            member.wake_by_ref();
+        }
+    }
+}
+
+/// One entry of the interest list
+struct Interest {
+    file: Weak<OpenFile>,
+    /// Requested events plus EPOLL_FLAGS
+    events: u32,
+    data: u64,
+    /// Cleared after an EPOLLONESHOT report until EPOLL_CTL_MOD re-arms it
+    enabled: bool,
+    /// Events seen on the last scan, so edge-triggered entries report changes only
+    last: IoEvents,
+    waker: Arc<InterestWaker>,
+    /// Registers in place of `waker` for EPOLLEXCLUSIVE entries
+    exclusive: Option<Arc<ExclusiveGroup>>,
+}
+
+impl Interest {
+    /// Events to report: the requested ones plus errors and hangups, which are always reported
+    fn wanted(&self) -> IoEvents {
+        IoEvents::from_bits_truncate((self.events & !EPOLL_FLAGS) | EPOLLERR | EPOLLHUP)
+    }
+
+    /// Register the waker with the watched object unless it is still registered
+    fn arm(&self, file: &OpenFile) {
+        if let Some(group) = &self.exclusive {
+            group.arm(file);
+        } else if !self.waker.armed.swap(true, Ordering::AcqRel) {
+            let waker = Waker::from(self.waker.clone());
+            file.object.register(&mut Context::from_waker(&waker), self.wanted());
+        }
+    }
+
+    /// Events this entry would report now, without consuming anything
+    fn pending(&self, file: &OpenFile) -> IoEvents {
+        if !self.enabled {
+            return IoEvents::empty();
+        }
+        let dirty = self.waker.dirty.load(Ordering::Acquire);
+        // An exclusive entry only reports after its group picked it for a wakeup
+        if self.exclusive.is_some() && !dirty {
+            return IoEvents::empty();
+        }
+        let ready = file_events(file) & self.wanted();
+        let edge = self.events & EPOLLET != 0;
+        if edge && !dirty && ready == self.last {
+            return IoEvents::empty();
+        }
+        ready
+    }
+}
+
+struct EpollState {
+    interests: BTreeMap<Key, Interest>,
+    /// Key to start the next scan from, so a small maxevents does not starve later fds
+    cursor: Key,
+}
+
+/// An epoll instance
+pub struct Epoll {
+    state: SpinLock<EpollState>,
+    ready: Arc<PollSet>,
+}
+
+impl Epoll {
+    fn new() -> Self {
+        Self {
+            state: SpinLock::new(EpollState {
+                interests: BTreeMap::new(),
+                cursor: (0, 0),
+            }),
+            ready: Arc::new(PollSet::new()),
+        }
+    }
+
+    /// Whether `target` can be reached from this instance through nested epoll fds
+    fn reaches(&self, target: *const Epoll, depth: usize) -> Result<bool, LinuxError> {
+        if depth > MAX_NESTS {
+            return Err(LinuxError::ELOOP);
+        }
+        let files: Vec<Arc<OpenFile>> = self
+            .state
+            .lock()
+            .interests
+            .values()
+            .filter_map(|i| i.file.upgrade())
+            .collect();
+        for file in files {
+            if let Some(inner) = file.downcast_ref::<Epoll>() {
+                if core::ptr::eq(inner, target) || inner.reaches(target, depth + 1)? {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Apply an EPOLL_CTL_* operation
+    fn control(&self, op: u32, fd: i32, file: &Arc<OpenFile>, event: Option<epoll_event>) -> Result<(), LinuxError> {
+        let key = (fd, Arc::as_ptr(file) as usize);
+        let mut state = self.state.lock();
+        match op {
+            EPOLL_CTL_ADD => {
+                let event = event.ok_or(LinuxError::EFAULT)?;
+                if state.interests.contains_key(&key) {
+                    return Err(LinuxError::EEXIST);
+                }
+                let waker = Arc::new(InterestWaker {
+                    armed: AtomicBool::new(false),
+                    // Readiness at the time of adding is reported too
+                    dirty: AtomicBool::new(true),
+                    ready: self.ready.clone(),
+                });
+                let exclusive = (event.events & EPOLLEXCLUSIVE != 0)
+                    .then(|| ExclusiveGroup::join(file, &waker));
+                let interest = Interest {
+                    file: Arc::downgrade(file),
+                    events: event.events & !EPOLLWAKEUP,
+                    data: event.data,
+                    enabled: true,
+                    last: IoEvents::empty(),
+                    waker,
+                    exclusive,
+                };
+                interest.arm(file);
+                state.interests.insert(key, interest);
+            }
+            EPOLL_CTL_MOD => {
+                let event = event.ok_or(LinuxError::EFAULT)?;
+                let interest = state.interests.get_mut(&key).ok_or(LinuxError::ENOENT)?;
+                if event.events & EPOLLEXCLUSIVE != 0 || interest.events & EPOLLEXCLUSIVE != 0 {
+                    return Err(LinuxError::EINVAL);
+                }
+                interest.events = event.events & !EPOLLWAKEUP;
+                interest.data = event.data;
+                interest.enabled = true;
+                interest.last = IoEvents::empty();
+                interest.waker.dirty.store(true, Ordering::Release);
+                interest.arm(file);
+            }
+            EPOLL_CTL_DEL => {
+                state.interests.remove(&key).ok_or(LinuxError::ENOENT)?;
+            }
+            _ => return Err(LinuxError::EINVAL),
+        }
+        drop(state);
+        // A waiter may now have something to report
+        self.ready.wake();
+        Ok(())
+    }
+
+    /// Move up to `max` ready events into `out`, dropping entries whose file is gone
+    fn collect(&self, out: &mut Vec<epoll_event>, max: usize) -> usize {
+        let mut state = self.state.lock();
+        let cursor = state.cursor;
+        let keys: Vec<Key> = state
+            .interests
+            .range(cursor..)
+            .chain(state.interests.range(..cursor))
+            .map(|(key, _)| *key)
+            .collect();
+
+        for key in keys {
+            if out.len() >= max {
+                state.cursor = key;
+                break;
+            }
+            let Some(interest) = state.interests.get_mut(&key) else {
+                continue;
+            };
+            let Some(file) = interest.file.upgrade() else {
+                state.interests.remove(&key);
+                continue;
+            };
+            interest.arm(&file);
+            let ready = interest.pending(&file);
+            interest.last = file_events(&file) & interest.wanted();
+            if ready.is_empty() {
+                // A picked exclusive entry with nothing to report waits for the next pick
+                if interest.exclusive.is_some() {
+                    interest.waker.dirty.store(false, Ordering::Release);
+                }
+                continue;
+            }
+            // Level-triggered exclusive entries keep reporting while ready, as on Linux
+            if interest.exclusive.is_none() || interest.events & EPOLLET != 0 {
+                interest.waker.dirty.store(false, Ordering::Release);
+            }
+            if interest.events & EPOLLONESHOT != 0 {
+                interest.enabled = false;
+            }
+            out.push(epoll_event {
+                events: ready.bits(),
+                data: interest.data,
+            });
+        }
+        out.len()
+    }
+}
+
+impl FileLike for Epoll {
+    fn read(&self, _buf: &mut [u8], _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EINVAL)
+    }
+
+    fn write(&self, _buf: &[u8], _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EINVAL)
+    }
+
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        Ok(anon_inode_metadata(self as *const Self as u64))
+    }
+
+    /// Readable while any entry has events to report
+    fn poll(&self) -> IoEvents {
+        let state = self.state.lock();
+        let ready = state.interests.values().any(|interest| {
+            interest
+                .file
+                .upgrade()
+                .is_some_and(|file| !interest.pending(&file).is_empty())
+        });
+        if ready {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+
+    fn register(&self, context: &mut Context<'_>, _events: IoEvents) {
+        self.ready.register(context.waker());
+        let state = self.state.lock();
+        for interest in state.interests.values() {
+            if let Some(file) = interest.file.upgrade() {
+                interest.arm(&file);
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Syscall: epoll_create1(2) - Create an epoll instance
+/// Supported flags: EPOLL_CLOEXEC
+/// Returns the new fd on success, negative error on failure
+pub fn sys_epoll_create1(flags: u32) -> isize {
+    if flags & !EPOLL_CLOEXEC != 0 {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    let fd_flags = if flags & EPOLL_CLOEXEC != 0 { FD_CLOEXEC } else { 0 };
+    match fd_table::add_file(Epoll::new(), O_RDWR, fd_flags) {
+        Ok(fd) => fd as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: epoll_ctl(2) - Add, modify or remove an entry of an epoll interest list
+/// Regular files and directories are always ready, so Linux refuses them with EPERM.
+/// With EPOLLEXCLUSIVE, each event on the object wakes only one of the epoll
+/// instances that added it exclusively.
+/// Returns 0 on success, negative error on failure
+pub fn sys_epoll_ctl(epfd: i32, op: u32, fd: i32, event: usize) -> isize {
+    let res = (|| -> Result<(), LinuxError> {
+        let epfile = fd_table::get_file(epfd).ok_or(LinuxError::EBADF)?;
+        let file = fd_table::get_file(fd).ok_or(LinuxError::EBADF)?;
+        if file.status_flags() & O_PATH != 0 {
+            return Err(LinuxError::EBADF);
+        }
+        let epoll = epfile.downcast_ref::<Epoll>().ok_or(LinuxError::EINVAL)?;
+        if Arc::ptr_eq(&epfile, &file) {
+            return Err(LinuxError::EINVAL);
+        }
+        if file.downcast_ref::<File>().is_some() || file.downcast_ref::<Directory>().is_some() {
+            return Err(LinuxError::EPERM);
+        }
+
+        let event = if op == EPOLL_CTL_DEL || event == 0 {
+            None
+        } else {
+            Some(unsafe { (event as *const epoll_event).read_unaligned() })
+        };
+        if let Some(ev) = &event {
+            if ev.events & EPOLLEXCLUSIVE != 0 && op == EPOLL_CTL_ADD {
+                // Exclusive wakeups only make sense for plain in/out interest on a non-epoll file
+                if ev.events & !EPOLLEXCLUSIVE_OK != 0 || file.downcast_ref::<Epoll>().is_some() {
+                    return Err(LinuxError::EINVAL);
+                }
+            }
+        }
+        if op == EPOLL_CTL_ADD {
+            if let Some(inner) = file.downcast_ref::<Epoll>() {
+                if inner.reaches(epoll, 1)? {
+                    return Err(LinuxError::ELOOP);
+                }
+            }
+        }
+
+        epoll.control(op, fd, &file, event)
+    })();
+
+    match res {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Shared implementation of epoll_pwait and epoll_pwait2
+fn do_epoll_wait(
+    epfd: i32,
+    events: usize,
+    maxevents: i32,
+    timeout: Option<Duration>,
+    sigmask: usize,
+    sigsetsize: usize,
+) -> Result<usize, LinuxError> {
+    let max = maxevents as usize;
+    if maxevents <= 0 || max > i32::MAX as usize / core::mem::size_of::<epoll_event>() {
+        return Err(LinuxError::EINVAL);
+    }
+    let file = fd_table::get_file(epfd).ok_or(LinuxError::EBADF)?;
+    let epoll = file.downcast_ref::<Epoll>().ok_or(LinuxError::EINVAL)?;
+    let mask = signal::load_sigset(sigmask, sigsetsize)?;
+
+    let mut out = Vec::new();
+    let n = poll::with_sigmask(mask, || {
+        poll::wait_ready(timeout, |cx| {
+            if let Some(cx) = cx {
+                epoll.ready.register(cx.waker());
+            }
+            out.clear();
+            Ok(epoll.collect(&mut out, max))
+        })
+    })?;
+
+    let dst = unsafe { core::slice::from_raw_parts_mut(events as *mut epoll_event, n) };
+    dst.copy_from_slice(&out[..n]);
+    Ok(n)
+}
+
+/// Syscall: epoll_pwait(2) - Wait for events on an epoll instance
+/// `timeout` is in milliseconds; -1 waits forever.
+/// Returns the number of events stored, 0 on timeout, negative error on failure
+pub fn sys_epoll_pwait(
+    epfd: i32,
+    events: usize,
+    maxevents: i32,
+    timeout: i32,
+    sigmask: usize,
+    sigsetsize: usize,
+) -> isize {
+    let timeout = if timeout < 0 {
+        None
+    } else {
+        Some(Duration::from_millis(timeout as u64))
+    };
+    match do_epoll_wait(epfd, events, maxevents, timeout, sigmask, sigsetsize) {
+        Ok(n) => n as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: epoll_pwait2(2) - epoll_pwait with a timespec timeout (NULL waits forever)
+/// Returns the number of events stored, 0 on timeout, negative error on failure
+pub fn sys_epoll_pwait2(
+    epfd: i32,
+    events: usize,
+    maxevents: i32,
+    timeout: usize,
+    sigmask: usize,
+    sigsetsize: usize,
+) -> isize {
+    let res = poll::load_timeout(timeout)
+        .and_then(|timeout| do_epoll_wait(epfd, events, maxevents, timeout, sigmask, sigsetsize));
+    match res {
+        Ok(n) => n as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
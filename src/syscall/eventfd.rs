@@ -0,0 +1,150 @@
+/// Event counters (eventfd2)
+///
+/// An eventfd holds a 64-bit counter: write adds to it, read takes it (or one
+/// unit of it in EFD_SEMAPHORE mode). Readers wait while it is zero and
+/// writers while an addition would push it past u64::MAX - 1.
+
+use core::{any::Any, task::Context};
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::Metadata;
+use axpoll::{IoEvents, PollSet};
+use axtask::WaitQueue;
+use linux_raw_sys::general::{EFD_CLOEXEC, EFD_NONBLOCK, EFD_SEMAPHORE, O_RDWR};
+
+use super::{
+    fd_table::{self, SpinLock, FD_CLOEXEC, FD_NONBLOCK},
+    file::{anon_inode_metadata, FileLike},
+    linux_err_to_isize,
+};
+
+/// Largest value the counter can hold
+const MAX_COUNT: u64 = u64::MAX - 1;
+
+/// An eventfd object
+pub struct EventFd {
+    count: SpinLock<u64>,
+    semaphore: bool,
+    /// Woken when the counter becomes nonzero
+    read_ready: PollSet,
+    /// Woken when the counter drops
+    write_ready: PollSet,
+    /// Blocked readers and writers, woken by any change of the counter
+    changed: WaitQueue,
+}
+
+impl EventFd {
+    fn new(initval: u32, semaphore: bool) -> Self {
+        Self {
+            count: SpinLock::new(initval as u64),
+            semaphore,
+            read_ready: PollSet::new(),
+            write_ready: PollSet::new(),
+            changed: WaitQueue::new(),
+        }
+    }
+}
+
+impl FileLike for EventFd {
+    /// Take the counter (or 1 in semaphore mode) as a native-endian u64
+    fn read(&self, buf: &mut [u8], flags: u32) -> Result<usize, LinuxError> {
+        if buf.len() < 8 {
+            return Err(LinuxError::EINVAL);
+        }
+        loop {
+            {
+                let mut count = self.count.lock();
+                if *count > 0 {
+                    let value = if self.semaphore { 1 } else { *count };
+                    *count -= value;
+                    drop(count);
+                    buf[..8].copy_from_slice(&value.to_ne_bytes());
+                    self.write_ready.wake();
+                    self.changed.notify_all(false);
+                    return Ok(8);
+                }
+            }
+            if flags & FD_NONBLOCK != 0 {
+                return Err(LinuxError::EAGAIN);
+            }
+            self.changed.wait_until(|| *self.count.lock() > 0);
+        }
+    }
+
+    /// Add a native-endian u64 to the counter
+    fn write(&self, buf: &[u8], flags: u32) -> Result<usize, LinuxError> {
+        if buf.len() < 8 {
+            return Err(LinuxError::EINVAL);
+        }
+        let mut raw = [0u8; 8];
+        raw.copy_from_slice(&buf[..8]);
+        let value = u64::from_ne_bytes(raw);
+        if value == u64::MAX {
+            return Err(LinuxError::EINVAL);
+        }
+        loop {
+            {
+                let mut count = self.count.lock();
+                if MAX_COUNT - *count >= value {
+                    *count += value;
+                    drop(count);
+                    if value > 0 {
+                        self.read_ready.wake();
+                        self.changed.notify_all(false);
+                    }
+                    return Ok(8);
+                }
+            }
+            if flags & FD_NONBLOCK != 0 {
+                return Err(LinuxError::EAGAIN);
+            }
+            self.changed
+                .wait_until(|| MAX_COUNT - *self.count.lock() >= value);
+        }
+    }
+
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        Ok(anon_inode_metadata(self as *const Self as u64))
+    }
+
+    /// Readable while the counter is nonzero, writable while 1 can still be added
+    fn poll(&self) -> IoEvents {
+        let count = *self.count.lock();
+        let mut events = IoEvents::empty();
+        if count > 0 {
+            events |= IoEvents::IN;
+        }
+        if count < MAX_COUNT {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+
+    fn register(&self, context: &mut Context<'_>, events: IoEvents) {
+        if events.contains(IoEvents::IN) {
+            self.read_ready.register(context.waker());
+        }
+        if events.contains(IoEvents::OUT) {
+            self.write_ready.register(context.waker());
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Syscall: eventfd2(2) - Create an event counter fd
+/// Supported flags: EFD_SEMAPHORE, EFD_NONBLOCK, EFD_CLOEXEC
+/// Returns the new fd on success, negative error on failure
+pub fn sys_eventfd2(initval: u32, flags: u32) -> isize {
+    if flags & !(EFD_SEMAPHORE | EFD_NONBLOCK | EFD_CLOEXEC) != 0 {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    let fd_flags = if flags & EFD_CLOEXEC != 0 { FD_CLOEXEC } else { 0 };
+    let eventfd = EventFd::new(initval, flags & EFD_SEMAPHORE != 0);
+    match fd_table::add_file(eventfd, O_RDWR | (flags & EFD_NONBLOCK), fd_flags) {
+        Ok(fd) => fd as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
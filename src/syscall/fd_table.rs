@@ -2,13 +2,21 @@
 /// 
 /// Manages open file descriptors using a simple Vec-based table with dynamic growth.
 
-use alloc::vec::Vec;
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
 use core::{
     cell::UnsafeCell,
-    sync::atomic::{AtomicBool, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
 };
 
-use axfs::File;
+use axerrno::LinuxError;
+
+use axfs_ng_vfs::{Location, Metadata};
+use linux_raw_sys::general::{
+    FASYNC, IN_CLOSE_NOWRITE, IN_CLOSE_WRITE, IN_MODIFY, O_ACCMODE, O_APPEND, O_DIRECT,
+    O_NOATIME, O_NONBLOCK, O_PATH, O_RDONLY, O_RDWR, O_WRONLY,
+};
+
+use super::{console::Console, dir::Directory, file::FileLike, inotify, lock};
 
 /// File descriptor flags
 pub const FD_CLOEXEC: u32 = 0x1;
@@ -16,20 +24,180 @@ pub const FD_CLOEXEC: u32 = 0x1;
 /// File status flags (subset of O_* flags)
 pub const FD_NONBLOCK: u32 = 0x800;
 
+/// Status flags F_SETFL may change; the access mode and the rest are fixed at open
+pub const SETFL_MASK: u32 = O_APPEND | O_NONBLOCK | FASYNC | O_DIRECT | O_NOATIME;
+
+/// Open file description
+///
+/// Shared by every fd created from the same open(2) through dup/dup3/F_DUPFD,
+/// so they see one file offset and one set of status flags, as on Linux.
+pub struct OpenFile {
+    pub object: Box<dyn FileLike>,
+    status_flags: AtomicU32,    // File status flags (e.g., O_NONBLOCK)
+}
+
+impl OpenFile {
+    pub fn new(object: impl FileLike, status_flags: u32) -> Self {
+        Self {
+            object: Box::new(object),
+            status_flags: AtomicU32::new(status_flags),
+        }
+    }
+
+    /// Get the object as its concrete type, if it is a `T`
+    pub fn downcast_ref<T: FileLike>(&self) -> Option<&T> {
+        self.object.as_any().downcast_ref::<T>()
+    }
+
+    /// Check the access mode allows reading (EBADF otherwise, as on Linux)
+    pub fn check_readable(&self) -> Result<(), LinuxError> {
+        match self.status_flags() & (O_ACCMODE | O_PATH) {
+            O_RDONLY | O_RDWR => Ok(()),
+            _ => Err(LinuxError::EBADF),
+        }
+    }
+
+    /// Check the access mode allows writing (EBADF otherwise, as on Linux)
+    pub fn check_writable(&self) -> Result<(), LinuxError> {
+        match self.status_flags() & (O_ACCMODE | O_PATH) {
+            O_WRONLY | O_RDWR => Ok(()),
+            _ => Err(LinuxError::EBADF),
+        }
+    }
+
+    /// Read at the current position of whatever object this is (read, readv)
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, LinuxError> {
+        self.read_with_flags(buf, 0)
+    }
+
+    /// Read as `read` does, with status flags `extra` added for this call only
+    /// (the RWF_* flags of preadv2)
+    pub fn read_with_flags(&self, buf: &mut [u8], extra: u32) -> Result<usize, LinuxError> {
+        self.check_readable()?;
+        self.object.read(buf, self.status_flags() | extra)
+    }
+
+    /// Write at the current position of whatever object this is (write, writev)
+    pub fn write(&self, buf: &[u8]) -> Result<usize, LinuxError> {
+        self.write_with_flags(buf, 0)
+    }
+
+    /// Write as `write` does, with status flags `extra` added for this call only
+    /// (the RWF_* flags of pwritev2)
+    pub fn write_with_flags(&self, buf: &[u8], extra: u32) -> Result<usize, LinuxError> {
+        self.check_writable()?;
+        let n = self.object.write(buf, self.status_flags() | extra)?;
+        self.notify_modified(n);
+        Ok(n)
+    }
+
+    /// Check the object supports positional I/O (ESPIPE, EISDIR, ... otherwise)
+    pub fn check_positional(&self) -> Result<(), LinuxError> {
+        self.object.read_at(&mut [], 0).map(|_| ())
+    }
+
+    /// Read at `offset` without moving the position (pread64, preadv)
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize, LinuxError> {
+        self.check_readable()?;
+        self.object.read_at(buf, offset)
+    }
+
+    /// Write at `offset` without moving the position (pwrite64, pwritev)
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> Result<usize, LinuxError> {
+        self.check_writable()?;
+        let n = self.object.write_at(buf, offset, self.status_flags())?;
+        self.notify_modified(n);
+        Ok(n)
+    }
+
+    /// Report IN_MODIFY to inotify watchers after `written` bytes went to a VFS node
+    fn notify_modified(&self, written: usize) {
+        if written > 0 {
+            if let Some(loc) = self.location() {
+                inotify::notify_node(loc, IN_MODIFY);
+            }
+        }
+    }
+
+    /// Get the VFS node this description refers to, if it has one
+    pub fn location(&self) -> Option<&Location> {
+        self.object.location()
+    }
+
+    /// Get the metadata fstat reports for this description
+    pub fn metadata(&self) -> Result<Metadata, LinuxError> {
+        self.object.stat()
+    }
+
+    /// Get the open directory for listing (getdents64)
+    pub fn as_directory(&self) -> Result<&Directory, LinuxError> {
+        if self.status_flags() & O_PATH != 0 {
+            return Err(LinuxError::EBADF);
+        }
+        self.downcast_ref::<Directory>().ok_or(LinuxError::ENOTDIR)
+    }
+
+    /// Get the directory to start a relative lookup from (dirfd of *at syscalls)
+    pub fn as_dir(&self) -> Result<&Location, LinuxError> {
+        self.location()
+            .filter(|loc| loc.is_dir())
+            .ok_or(LinuxError::ENOTDIR)
+    }
+
+    pub fn status_flags(&self) -> u32 {
+        self.status_flags.load(Ordering::Relaxed)
+    }
+
+    pub fn set_status_flags(&self, flags: u32) {
+        self.status_flags.store(flags, Ordering::Relaxed);
+    }
+
+    /// Replace the status flags selected by `mask` with those in `flags`
+    pub fn update_status_flags(&self, mask: u32, flags: u32) {
+        let _ = self
+            .status_flags
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+                Some((old & !mask) | (flags & mask))
+            });
+    }
+}
+
+impl Drop for OpenFile {
+    /// The last fd sharing this description is gone: drop its OFD and flock locks
+    /// and report the close to inotify watchers
+    fn drop(&mut self) {
+        lock::release_file(self);
+        let flags = self.status_flags();
+        if flags & O_PATH != 0 {
+            return;
+        }
+        if let Some(loc) = self.location() {
+            let mask = if flags & O_ACCMODE == O_RDONLY {
+                IN_CLOSE_NOWRITE
+            } else {
+                IN_CLOSE_WRITE
+            };
+            inotify::notify_node(loc, mask);
+        }
+    }
+}
+
 /// File entry with metadata
 pub struct FileEntry {
-    pub file: File,
-    pub fd_flags: u32,      // FD_* flags (e.g., FD_CLOEXEC)
-    pub file_flags: u32,    // File status flags (e.g., O_NONBLOCK)
+    pub file: Arc<OpenFile>,
+    pub fd_flags: u32,      // FD_* flags (e.g., FD_CLOEXEC), private to this fd
 }
 
 impl FileEntry {
-    pub fn new(file: File) -> Self {
-        Self {
-            file,
-            fd_flags: 0,
-            file_flags: 0,
-        }
+    pub fn new(file: Arc<OpenFile>, fd_flags: u32) -> Self {
+        Self { file, fd_flags }
+    }
+}
+
+impl Drop for FileEntry {
+    /// Closing any fd of a node drops the process's byte-range locks on it
+    fn drop(&mut self) {
+        lock::release_posix(&self.file);
     }
 }
 
@@ -81,36 +249,157 @@ impl<T> Drop for SpinLockGuard<'_, T> {
     }
 }
 
+/// Default RLIMIT_NOFILE (soft, hard), matching Linux
+const DEFAULT_NOFILE: (u64, u64) = (1024, 1048576);
+
+/// Largest value RLIMIT_NOFILE can be raised to (fs.nr_open)
+pub const NR_OPEN: u64 = 1048576;
+
+/// Two-level bitmap of allocated fds
+///
+/// `words` has one bit per fd; `full` has one bit per word of `words` that is
+/// completely in use, so the search for the lowest free fd skips 4096 busy
+/// fds per summary word instead of probing each slot.
+struct FdBitmap {
+    words: Vec<u64>,
+    full: Vec<u64>,
+}
+
+impl FdBitmap {
+    const fn new() -> Self {
+        Self {
+            words: Vec::new(),
+            full: Vec::new(),
+        }
+    }
+
+    fn set(&mut self, fd: usize) {
+        let w = fd / 64;
+        if w >= self.words.len() {
+            self.words.resize(w + 1, 0);
+            self.full.resize(self.words.len().div_ceil(64), 0);
+        }
+        self.words[w] |= 1 << (fd % 64);
+        if self.words[w] == u64::MAX {
+            self.full[w / 64] |= 1 << (w % 64);
+        }
+    }
+
+    fn clear(&mut self, fd: usize) {
+        let w = fd / 64;
+        if w < self.words.len() {
+            self.words[w] &= !(1 << (fd % 64));
+            self.full[w / 64] &= !(1 << (w % 64));
+        }
+    }
+
+    /// Find the lowest set bit that is >= `from`, skipping empty words
+    fn next_set(&self, from: usize) -> Option<usize> {
+        let mut w = from / 64;
+        let mut mask = u64::MAX << (from % 64);
+        while w < self.words.len() {
+            let bits = self.words[w] & mask;
+            if bits != 0 {
+                return Some(w * 64 + bits.trailing_zeros() as usize);
+            }
+            w += 1;
+            mask = u64::MAX;
+        }
+        None
+    }
+
+    /// Find the lowest clear bit that is >= `min`
+    fn first_free(&self, min: usize) -> usize {
+        let len = self.words.len();
+        let mut w = min / 64;
+        
+        // The word containing `min` is only partially eligible
+        if w < len {
+            let free = !self.words[w] & (u64::MAX << (min % 64));
+            if free != 0 {
+                return w * 64 + free.trailing_zeros() as usize;
+            }
+            w += 1;
+        }
+        
+        // Use the summary level to jump to the next word with a free bit
+        while w < len {
+            let s = w / 64;
+            let not_full = !self.full[s] & (u64::MAX << (w % 64));
+            if not_full != 0 {
+                let candidate = s * 64 + not_full.trailing_zeros() as usize;
+                if candidate >= len {
+                    break;
+                }
+                return candidate * 64 + (!self.words[candidate]).trailing_zeros() as usize;
+            }
+            w = (s + 1) * 64;
+        }
+        
+        min.max(len * 64)
+    }
+}
+
 /// File descriptor table
 pub struct FdTable {
     entries: Vec<Option<FileEntry>>,
+    used: FdBitmap,
+    /// RLIMIT_NOFILE (soft, hard)
+    nofile: (u64, u64),
+    stdio_installed: bool,
 }
 
 impl FdTable {
     pub const fn new() -> Self {
         Self {
             entries: Vec::new(),
+            used: FdBitmap::new(),
+            nofile: DEFAULT_NOFILE,
+            stdio_installed: false,
         }
     }
 
-    /// Insert a file and return its file descriptor
-    fn insert(&mut self, file: File) -> i32 {
-        for (idx, entry) in self.entries.iter_mut().enumerate() {
-            if entry.is_none() {
-                *entry = Some(FileEntry::new(file));
-                return idx as i32;
-            }
+    /// Install the console at fds 0, 1 and 2.
+    /// They share one open file description, like a terminal inherited from a shell.
+    fn install_stdio(&mut self) {
+        let console = Arc::new(OpenFile::new(Console, O_RDWR));
+        for fd in 0..3 {
+            self.install(fd, FileEntry::new(console.clone(), 0));
         }
-        self.entries.push(Some(FileEntry::new(file)));
-        (self.entries.len() - 1) as i32
+        self.stdio_installed = true;
     }
 
-    /// Get mutable reference to a file by fd
-    fn get_mut(&mut self, fd: i32) -> Option<&mut File> {
-        if fd < 0 {
-            return None;
+    /// Install an entry at `fd`, growing the table if needed.
+    /// Returns the entry previously installed there, if any.
+    fn install(&mut self, fd: usize, entry: FileEntry) -> Option<FileEntry> {
+        if fd >= self.entries.len() {
+            self.entries.resize_with(fd + 1, || None);
+        }
+        self.used.set(fd);
+        self.entries[fd].replace(entry)
+    }
+
+    /// Claim the lowest free fd >= `min` without installing anything there yet.
+    /// Fails with EMFILE when that fd would reach RLIMIT_NOFILE.
+    fn reserve(&mut self, min: usize) -> Result<usize, LinuxError> {
+        let fd = self.used.first_free(min);
+        if fd as u64 >= self.nofile.0 {
+            return Err(LinuxError::EMFILE);
+        }
+        self.used.set(fd);
+        Ok(fd)
+    }
+
+    /// Check whether `fd` is claimed by `reserve` but not installed yet
+    fn is_reserved(&self, fd: usize) -> bool {
+        self.used.next_set(fd) == Some(fd) && self.get_entry(fd as i32).is_none()
+    }
+
+    /// Give back a reserved fd that was never installed
+    fn unreserve(&mut self, fd: usize) {
+        if self.is_reserved(fd) {
+            self.used.clear(fd);
         }
-        self.entries.get_mut(fd as usize)?.as_mut().map(|entry| &mut entry.file)
     }
 
     /// Get reference to a file entry by fd
@@ -129,47 +418,143 @@ impl FdTable {
         self.entries.get_mut(fd as usize)?.as_mut()
     }
 
-    /// Remove and return a file by fd
-    fn remove(&mut self, fd: i32) -> Option<File> {
+    /// Open fds in `first..=last`, in ascending order
+    fn open_in_range(&self, first: usize, last: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut next = first;
+        core::iter::from_fn(move || {
+            let fd = self.used.next_set(next).filter(|&fd| fd <= last)?;
+            next = fd + 1;
+            Some(fd)
+        })
+    }
+
+    /// Remove and return a file entry by fd
+    fn remove(&mut self, fd: i32) -> Option<FileEntry> {
         if fd < 0 {
             return None;
         }
-        let entry = self.entries.get_mut(fd as usize)?;
-        entry.take().map(|e| e.file)
+        let entry = self.entries.get_mut(fd as usize)?.take()?;
+        self.used.clear(fd as usize);
+        Some(entry)
     }
 }
 
 static FD_TABLE: SpinLock<FdTable> = SpinLock::new(FdTable::new());
 
-/// Add a file to the fd table and return its fd
-pub fn add_file(file: File) -> i32 {
-    FD_TABLE.lock().insert(file)
+/// Lock the fd table, creating the standard streams on first use
+fn lock_table() -> SpinLockGuard<'static, FdTable> {
+    let mut table = FD_TABLE.lock();
+    if !table.stdio_installed {
+        table.install_stdio();
+    }
+    table
 }
 
-/// Execute a closure with mutable access to a file
-pub fn with_file_mut<R, F>(fd: i32, f: F) -> Option<R>
-where
-    F: FnOnce(&mut File) -> R,
-{
-    let mut table = FD_TABLE.lock();
-    let file = table.get_mut(fd)?;
-    Some(f(file))
+/// An fd claimed before the object it will refer to exists
+///
+/// openat reserves its fd first, so hitting RLIMIT_NOFILE fails before the
+/// open creates or truncates anything. The slot reads as closed until
+/// `install` fills it; dropping the reservation gives the fd back.
+pub struct ReservedFd {
+    fd: usize,
 }
 
-/// Remove a file from the fd table
-pub fn remove_file(fd: i32) -> Option<File> {
-    FD_TABLE.lock().remove(fd)
+impl ReservedFd {
+    /// Reserve the lowest free fd; EMFILE when RLIMIT_NOFILE is reached
+    pub fn new() -> Result<Self, LinuxError> {
+        let fd = lock_table().reserve(0)?;
+        Ok(Self { fd })
+    }
+
+    /// Install `object` at the reserved fd and return that fd.
+    /// `status_flags` holds the access mode and O_* status flags reported by
+    /// F_GETFL; `fd_flags` the FD_* flags of the new fd.
+    pub fn install(self, object: impl FileLike, status_flags: u32, fd_flags: u32) -> i32 {
+        let file = Arc::new(OpenFile::new(object, status_flags));
+        let fd = self.fd;
+        lock_table().install(fd, FileEntry::new(file, fd_flags));
+        // The slot is in use now; nothing to give back
+        core::mem::forget(self);
+        fd as i32
+    }
+}
+
+impl Drop for ReservedFd {
+    fn drop(&mut self) {
+        lock_table().unreserve(self.fd);
+    }
+}
+
+/// Add a file object to the fd table and return its fd.
+/// `status_flags` holds the access mode and O_* status flags reported by
+/// F_GETFL; `fd_flags` the FD_* flags of the new fd.
+/// Fails with EMFILE when RLIMIT_NOFILE is reached.
+pub fn add_file(object: impl FileLike, status_flags: u32, fd_flags: u32) -> Result<i32, LinuxError> {
+    Ok(ReservedFd::new()?.install(object, status_flags, fd_flags))
+}
+
+/// Get the open file description behind a file descriptor.
+///
+/// The table lock is released before returning, so the caller may block
+/// on the file without holding up other fd operations.
+pub fn get_file(fd: i32) -> Option<Arc<OpenFile>> {
+    let table = lock_table();
+    table.get_entry(fd).map(|entry| entry.file.clone())
+}
+
+/// Get every open file description in the table (each one once per fd)
+pub fn open_files() -> Vec<Arc<OpenFile>> {
+    let table = lock_table();
+    table
+        .entries
+        .iter()
+        .flatten()
+        .map(|entry| entry.file.clone())
+        .collect()
+}
+
+/// Remove a file from the fd table.
+/// The open file description is released once its last fd is gone.
+pub fn remove_file(fd: i32) -> Option<Arc<OpenFile>> {
+    let entry = lock_table().remove(fd)?;
+    // The guard is gone: the entry drops (releasing POSIX locks) with the table unlocked
+    Some(entry.file.clone())
+}
+
+/// Close every open fd in `first..=last` (close_range).
+/// Only allocated slots are visited, so a huge `last` costs nothing extra.
+pub fn remove_range(first: usize, last: usize) {
+    let mut table = lock_table();
+    let fds: Vec<usize> = table.open_in_range(first, last).collect();
+    let removed: Vec<FileEntry> = fds
+        .into_iter()
+        .filter_map(|fd| table.remove(fd as i32))
+        .collect();
+    // Release the descriptions only after the table is unlocked
+    drop(table);
+    drop(removed);
+}
+
+/// Set FD_CLOEXEC on every open fd in `first..=last` (close_range CLOSE_RANGE_CLOEXEC)
+pub fn set_cloexec_range(first: usize, last: usize) {
+    let mut table = lock_table();
+    let fds: Vec<usize> = table.open_in_range(first, last).collect();
+    for fd in fds {
+        if let Some(entry) = table.get_entry_mut(fd as i32) {
+            entry.fd_flags |= FD_CLOEXEC;
+        }
+    }
 }
 
 /// Get FD flags for a file descriptor
 pub fn get_fd_flags(fd: i32) -> Option<u32> {
-    let table = FD_TABLE.lock();
+    let table = lock_table();
     table.get_entry(fd).map(|entry| entry.fd_flags)
 }
 
 /// Set FD flags for a file descriptor
 pub fn set_fd_flags(fd: i32, flags: u32) -> bool {
-    let mut table = FD_TABLE.lock();
+    let mut table = lock_table();
     if let Some(entry) = table.get_entry_mut(fd) {
         entry.fd_flags = flags;
         true
@@ -180,60 +565,71 @@ pub fn set_fd_flags(fd: i32, flags: u32) -> bool {
 
 /// Get file status flags for a file descriptor
 pub fn get_file_flags(fd: i32) -> Option<u32> {
-    let table = FD_TABLE.lock();
-    table.get_entry(fd).map(|entry| entry.file_flags)
+    let table = lock_table();
+    table.get_entry(fd).map(|entry| entry.file.status_flags())
 }
 
-/// Set file status flags for a file descriptor
+/// Set file status flags for a file descriptor (F_SETFL).
+/// Only the bits in SETFL_MASK change; the access mode and the rest are kept.
+/// The flags live in the open file description, so every dup'd fd sees the change.
 pub fn set_file_flags(fd: i32, flags: u32) -> bool {
-    let mut table = FD_TABLE.lock();
-    if let Some(entry) = table.get_entry_mut(fd) {
-        entry.file_flags = flags;
+    let table = lock_table();
+    if let Some(entry) = table.get_entry(fd) {
+        entry.file.update_status_flags(SETFL_MASK, flags);
         true
     } else {
         false
     }
 }
 
-/// Duplicate a file descriptor (for dup, dup2, dup3)
-/// Returns the new fd, or -1 if source fd is invalid
-pub fn dup_fd(old_fd: i32, new_fd: Option<i32>) -> i32 {
-    // We can't actually clone axfs::File, so we'll use a workaround:
-    // For now, just return the same fd since we're single-threaded
-    // In a real implementation, we'd need to keep a reference count or handle sharing
-    // But for basic dup2 semantics in single-process mode, we can reuse the same fd
-    
-    let mut table = FD_TABLE.lock();
-    
-    // Check source fd exists
-    if table.get_entry(old_fd).is_none() {
-        return -1;
-    }
-    
-    // If no specific target fd, find next free
-    if new_fd.is_none() {
-        // Find first free slot
-        for (idx, entry) in table.entries.iter_mut().enumerate() {
-            if entry.is_none() {
-                // Can't actually clone File, so this is simplified
-                // In production, would need File to be Clone or use Arc<File>
-                return idx as i32;
-            }
-        }
-        // No free slot, would need to implement actual duplication
-        // For now, return error
-        return -1;
+/// Duplicate `old_fd` onto the lowest free fd >= `min` (dup, F_DUPFD, F_DUPFD_CLOEXEC).
+/// A `min` at or above RLIMIT_NOFILE is EINVAL; no free fd below it is EMFILE.
+pub fn dup_fd(old_fd: i32, min: usize, cloexec: bool) -> Result<i32, LinuxError> {
+    let mut table = lock_table();
+    let file = table.get_entry(old_fd).ok_or(LinuxError::EBADF)?.file.clone();
+    if min as u64 >= table.nofile.0 {
+        return Err(LinuxError::EINVAL);
     }
-    
-    // For specific target fd (dup2 semantics)
-    let target = new_fd.unwrap();
-    if target < 0 {
-        return -1;
+    let fd_flags = if cloexec { FD_CLOEXEC } else { 0 };
+    let fd = table.reserve(min)?;
+    table.install(fd, FileEntry::new(file, fd_flags));
+    Ok(fd as i32)
+}
+
+/// Duplicate `old_fd` onto exactly `new_fd` (dup2, dup3).
+/// Whatever was open at `new_fd` is closed atomically as part of the switch.
+pub fn dup_fd_to(old_fd: i32, new_fd: i32, cloexec: bool) -> Result<i32, LinuxError> {
+    let mut table = lock_table();
+    let file = table.get_entry(old_fd).ok_or(LinuxError::EBADF)?.file.clone();
+    if new_fd < 0 || new_fd as u64 >= table.nofile.0 {
+        return Err(LinuxError::EBADF);
     }
-    
-    // target fd would need to be closed first if it exists
-    // Again, limited by File not being Clone-able
-    
-    target
+    // Another thread is between reserving this fd and installing its file
+    if table.is_reserved(new_fd as usize) {
+        return Err(LinuxError::EBUSY);
+    }
+    let fd_flags = if cloexec { FD_CLOEXEC } else { 0 };
+    let old = table.install(new_fd as usize, FileEntry::new(file, fd_flags));
+    // Drop the replaced description only after the table is unlocked
+    drop(table);
+    drop(old);
+    Ok(new_fd)
+}
+
+/// Get RLIMIT_NOFILE as (soft, hard)
+pub fn nofile_limit() -> (u64, u64) {
+    lock_table().nofile
 }
 
+/// Set RLIMIT_NOFILE.
+/// Already-open fds above a lowered soft limit stay open, as on Linux.
+pub fn set_nofile_limit(soft: u64, hard: u64) -> Result<(), LinuxError> {
+    if soft > hard {
+        return Err(LinuxError::EINVAL);
+    }
+    if hard > NR_OPEN {
+        return Err(LinuxError::EPERM);
+    }
+    lock_table().nofile = (soft, hard);
+    Ok(())
+}
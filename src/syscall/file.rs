@@ -0,0 +1,262 @@
+/// File-like objects held by the fd table
+///
+/// Every kind of fd (regular file, directory, O_PATH handle, console, pipe, ...)
+/// implements `FileLike`. Handlers call the trait instead of matching on the
+/// kind; an operation that makes no sense for a kind returns the errno Linux
+/// reports for it, which is what the default methods do.
+
+use alloc::string::String;
+use core::{any::Any, task::Context, time::Duration};
+
+use axerrno::LinuxError;
+use axfs::File;
+use axfs_ng_vfs::{DeviceId, Location, Metadata, NodePermission, NodeType};
+use axio::{Seek, SeekFrom};
+use axpoll::IoEvents;
+use linux_raw_sys::general::{O_APPEND, O_DSYNC, O_SYNC};
+
+use super::ax_err_to_linux;
+
+/// Operations on the object behind an open file description
+///
+/// `flags` arguments are the status flags of the description the call came
+/// through (O_NONBLOCK, O_APPEND, O_SYNC, ...), since several descriptions
+/// can share one object.
+pub trait FileLike: Any + Send + Sync {
+    /// Read at the current position (read, readv)
+    fn read(&self, buf: &mut [u8], flags: u32) -> Result<usize, LinuxError>;
+
+    /// Write at the current position (write, writev)
+    fn write(&self, buf: &[u8], flags: u32) -> Result<usize, LinuxError>;
+
+    /// Read at `offset` without moving the position (pread64, preadv)
+    fn read_at(&self, _buf: &mut [u8], _offset: u64) -> Result<usize, LinuxError> {
+        Err(LinuxError::ESPIPE)
+    }
+
+    /// Write at `offset` without moving the position (pwrite64, pwritev)
+    fn write_at(&self, _buf: &[u8], _offset: u64, _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::ESPIPE)
+    }
+
+    /// Reposition the object (lseek)
+    fn seek(&self, _pos: SeekFrom) -> Result<u64, LinuxError> {
+        Err(LinuxError::ESPIPE)
+    }
+
+    /// Set the size, zero-filling when it grows (ftruncate)
+    fn truncate(&self, _len: u64) -> Result<(), LinuxError> {
+        Err(LinuxError::EINVAL)
+    }
+
+    /// Metadata reported by fstat
+    fn stat(&self) -> Result<Metadata, LinuxError>;
+
+    /// Device-specific control (ioctl)
+    fn ioctl(&self, _cmd: u32, _arg: usize) -> Result<isize, LinuxError> {
+        Err(LinuxError::ENOTTY)
+    }
+
+    /// Events that are ready right now (poll, select, epoll)
+    fn poll(&self) -> IoEvents;
+
+    /// Arrange for `context` to be woken when one of `events` may have become ready.
+    /// Objects without a wakeup source leave this empty; waiters re-poll them periodically.
+    fn register(&self, _context: &mut Context<'_>, _events: IoEvents) {}
+
+    /// Fill a new mapping with the contents at `offset` (mmap)
+    fn mmap(&self, _buf: &mut [u8], _offset: u64) -> Result<(), LinuxError> {
+        Err(LinuxError::ENODEV)
+    }
+
+    /// Map the object's own memory at `offset` for a MAP_SHARED mapping of `len` bytes,
+    /// so stores through the mapping and writes through fds see each other.
+    /// Objects without such memory refuse with ENODEV; read-only shared mappings of
+    /// them fall back to a copy made by `mmap`.
+    fn mmap_shared(
+        &self,
+        _offset: u64,
+        _len: usize,
+        _writable: bool,
+    ) -> Result<*mut u8, LinuxError> {
+        Err(LinuxError::ENODEV)
+    }
+
+    /// Flush to backing storage (fsync, fdatasync)
+    fn sync(&self, _data_only: bool) -> Result<(), LinuxError> {
+        Err(LinuxError::EINVAL)
+    }
+
+    /// VFS node this object refers to, if it has one
+    fn location(&self) -> Option<&Location> {
+        None
+    }
+
+    /// Name readlink reports for /proc/self/fd entries of objects without a VFS node
+    fn display_name(&self) -> Option<String> {
+        None
+    }
+
+    /// Access the concrete type, for operations only one kind supports
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Metadata fstat reports for objects without a VFS node (epoll, eventfd, ...),
+/// which Linux backs with a shared anonymous inode
+pub fn anon_inode_metadata(inode: u64) -> Metadata {
+    Metadata {
+        device: 0,
+        inode,
+        nlink: 1,
+        mode: NodePermission::from_bits_truncate(0o600),
+        node_type: NodeType::Unknown,
+        uid: 0,
+        gid: 0,
+        size: 0,
+        block_size: 4096,
+        blocks: 0,
+        rdev: DeviceId::new(0, 0),
+        atime: Duration::ZERO,
+        mtime: Duration::ZERO,
+        ctime: Duration::ZERO,
+    }
+}
+
+/// Flush `file` after a write through a description opened with O_SYNC or O_DSYNC
+fn sync_after_write(file: &File, flags: u32) -> Result<(), LinuxError> {
+    if flags & O_DSYNC != 0 {
+        // O_SYNC also covers metadata; O_DSYNC only what is needed to read the data back
+        File::sync(file, flags & O_SYNC != O_SYNC).map_err(ax_err_to_linux)?;
+    }
+    Ok(())
+}
+
+impl FileLike for File {
+    fn read(&self, buf: &mut [u8], _flags: u32) -> Result<usize, LinuxError> {
+        File::read(self, buf).map_err(ax_err_to_linux)
+    }
+
+    fn write(&self, buf: &[u8], flags: u32) -> Result<usize, LinuxError> {
+        // O_APPEND may have been turned on by F_SETFL after open
+        if flags & O_APPEND != 0 {
+            Seek::seek(&mut &*self, SeekFrom::End(0)).map_err(ax_err_to_linux)?;
+        }
+        let n = File::write(self, buf).map_err(ax_err_to_linux)?;
+        sync_after_write(self, flags)?;
+        Ok(n)
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize, LinuxError> {
+        File::read_at(self, buf, offset).map_err(ax_err_to_linux)
+    }
+
+    fn write_at(&self, buf: &[u8], offset: u64, flags: u32) -> Result<usize, LinuxError> {
+        let n = File::write_at(self, buf, offset).map_err(ax_err_to_linux)?;
+        sync_after_write(self, flags)?;
+        Ok(n)
+    }
+
+    fn seek(&self, pos: SeekFrom) -> Result<u64, LinuxError> {
+        Seek::seek(&mut &*self, pos).map_err(ax_err_to_linux)
+    }
+
+    fn truncate(&self, len: u64) -> Result<(), LinuxError> {
+        File::set_len(self, len).map_err(ax_err_to_linux)
+    }
+
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        File::location(self).metadata().map_err(ax_err_to_linux)
+    }
+
+    fn poll(&self) -> IoEvents {
+        // Regular files never block
+        IoEvents::IN | IoEvents::OUT
+    }
+
+    fn mmap(&self, buf: &mut [u8], offset: u64) -> Result<(), LinuxError> {
+        // Bytes past the end of the file stay zero
+        let mut done = 0;
+        while done < buf.len() {
+            let n = File::read_at(self, &mut buf[done..], offset + done as u64)
+                .map_err(ax_err_to_linux)?;
+            if n == 0 {
+                break;
+            }
+            done += n;
+        }
+        Ok(())
+    }
+
+    fn sync(&self, data_only: bool) -> Result<(), LinuxError> {
+        File::sync(self, data_only).map_err(ax_err_to_linux)
+    }
+
+    fn location(&self) -> Option<&Location> {
+        Some(File::location(self))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// O_PATH handle: only usable for path resolution and fd-level operations
+pub struct PathFile {
+    loc: Location,
+}
+
+impl PathFile {
+    pub fn new(loc: Location) -> Self {
+        Self { loc }
+    }
+}
+
+impl FileLike for PathFile {
+    fn read(&self, _buf: &mut [u8], _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn write(&self, _buf: &[u8], _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn read_at(&self, _buf: &mut [u8], _offset: u64) -> Result<usize, LinuxError> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn write_at(&self, _buf: &[u8], _offset: u64, _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn seek(&self, _pos: SeekFrom) -> Result<u64, LinuxError> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        self.loc.metadata().map_err(ax_err_to_linux)
+    }
+
+    fn ioctl(&self, _cmd: u32, _arg: usize) -> Result<isize, LinuxError> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn poll(&self) -> IoEvents {
+        IoEvents::NVAL
+    }
+
+    fn mmap(&self, _buf: &mut [u8], _offset: u64) -> Result<(), LinuxError> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn sync(&self, _data_only: bool) -> Result<(), LinuxError> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn location(&self) -> Option<&Location> {
+        Some(&self.loc)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -1,23 +1,29 @@
-use alloc::string::String;
+use alloc::{format, string::String};
+use core::sync::atomic::{AtomicU64, Ordering};
+
 use axerrno::{AxError, AxResult, LinuxError};
-use axfs::{FsContext, OpenOptions};
-use axfs_ng_vfs::VfsError;
-use axio::{Seek, SeekFrom};
-use linux_raw_sys::general::{stat, O_APPEND, O_CREAT, O_RDONLY, O_TRUNC, O_WRONLY, S_IFREG};
+use axfs::{File, FsContext, OpenOptions, OpenResult};
+use axfs_ng_vfs::{Location, NodeType};
+use axio::SeekFrom;
+use linux_raw_sys::general::{
+    FASYNC, IN_CREATE, IN_MODIFY, O_ACCMODE, O_APPEND, O_CLOEXEC, O_CREAT, O_DIRECT, O_DIRECTORY,
+    O_DSYNC, O_EXCL, O_LARGEFILE, O_NOATIME, O_NOFOLLOW, O_NONBLOCK, O_PATH, O_RDONLY, O_SYNC,
+    O_TMPFILE, O_TRUNC, O_WRONLY,
+};
 
-use super::{linux_err_to_isize, fd_table, ENOSYS};
+use super::{
+    ax_err_to_isize, ax_err_to_linux,
+    dir::Directory,
+    fd_table::{self, OpenFile, ReservedFd, FD_CLOEXEC},
+    file::PathFile,
+    inotify, linux_err_to_isize, lock,
+    memfd::MemFd,
+    path::{fs_context_at, get_fs_context},
+};
 
-pub const AT_FDCWD: isize = -100;
+pub use super::path::AT_FDCWD;
 const MAX_PATH: usize = 4096;
 
-/// Get the global filesystem context
-fn get_fs_context() -> AxResult<FsContext> {
-    axfs::ROOT_FS_CONTEXT
-        .get()
-        .cloned()
-        .ok_or(AxError::NotFound)
-}
-
 /// Load a null-terminated C string from user space
 pub fn load_user_cstring(ptr: usize) -> AxResult<String> {
     if ptr == 0 {
@@ -34,15 +40,28 @@ pub fn load_user_cstring(ptr: usize) -> AxResult<String> {
         bytes.push(byte);
     }
     
-    Err(AxError::InvalidInput) // Path too long
+    Err(AxError::NameTooLong)
 }
 
+/// Open flags that describe the open file description and are reported by
+/// F_GETFL; everything else in open(2) flags only affects the open itself
+const STATUS_FLAGS: u32 = O_ACCMODE
+    | O_APPEND
+    | O_NONBLOCK
+    | O_DSYNC
+    | O_SYNC
+    | FASYNC
+    | O_DIRECT
+    | O_LARGEFILE
+    | O_NOATIME
+    | O_PATH;
+
 /// Convert Linux open flags to OpenOptions
-fn make_open_options(flags: u32) -> OpenOptions {
+fn make_open_options(flags: u32, mode: u32) -> OpenOptions {
     let mut opts = OpenOptions::new();
     
     // Handle access mode (bits 0-1)
-    match flags & 0o3 {
+    match flags & O_ACCMODE {
         O_RDONLY => opts.read(true),
         O_WRONLY => opts.write(true),
         _ => opts.read(true).write(true),
@@ -56,68 +75,195 @@ fn make_open_options(flags: u32) -> OpenOptions {
         opts.truncate(true);
     }
     if flags & O_CREAT != 0 {
-        opts.create(true);
+        // O_EXCL makes the creation atomic: fail if the name already exists
+        if flags & O_EXCL != 0 {
+            opts.create_new(true);
+        } else {
+            opts.create(true);
+        }
+        opts.mode(mode & 0o7777);
+    }
+    if flags & O_DIRECTORY != 0 {
+        opts.directory(true);
+    }
+    if flags & O_NOFOLLOW != 0 {
+        opts.no_follow(true);
     }
     
     opts
 }
 
+/// Open an anonymous regular file in the directory `path` (O_TMPFILE).
+/// The file gets a temporary name that is unlinked right away, so it lives
+/// only as long as its fds, unless linkat(AT_EMPTY_PATH) gives it a name.
+/// The filesystem cannot create unnamed inodes, so unlike on Linux the file is
+/// not truly anonymous: it shows up as `.tmpfile.N` until the unlink. Names are
+/// claimed with O_EXCL, and one that is already taken is skipped.
+fn open_tmpfile(fs: &FsContext, path: &str, flags: u32, mode: u32) -> Result<File, LinuxError> {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    
+    if flags & O_ACCMODE == O_RDONLY {
+        return Err(LinuxError::EINVAL);
+    }
+    let dir = fs.resolve(path).map_err(ax_err_to_linux)?;
+    if !dir.is_dir() {
+        return Err(LinuxError::ENOTDIR);
+    }
+    let dir_fs = fs.with_current_dir(dir.clone()).map_err(ax_err_to_linux)?;
+    
+    let opts = make_open_options((flags & !O_TMPFILE) | O_CREAT | O_EXCL, mode);
+    loop {
+        let name = format!(".tmpfile.{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        match opts.open(&dir_fs, &name) {
+            Ok(OpenResult::File(f)) => {
+                dir.unlink(&name, false).map_err(ax_err_to_linux)?;
+                return Ok(f);
+            }
+            Ok(OpenResult::Dir(_)) | Err(AxError::AlreadyExists) => continue,
+            Err(e) => return Err(ax_err_to_linux(e)),
+        }
+    }
+}
+
+/// Open `path`, also reporting whether the open created it.
+/// O_CREAT without O_EXCL first tries an exclusive create, so the answer comes
+/// from the opens themselves rather than from a lookup that could race with them.
+fn open_reporting_creation(
+    fs: &FsContext,
+    path: &str,
+    flags: u32,
+    mode: u32,
+) -> AxResult<(OpenResult, bool)> {
+    if flags & (O_CREAT | O_EXCL) != O_CREAT {
+        let res = make_open_options(flags, mode).open(fs, path)?;
+        return Ok((res, flags & O_CREAT != 0));
+    }
+    match make_open_options(flags | O_EXCL, mode).open(fs, path) {
+        Err(AxError::AlreadyExists) => {}
+        res => return res.map(|res| (res, true)),
+    }
+    match make_open_options(flags & !O_CREAT, mode).open(fs, path) {
+        // A dangling symlink, or the name went away meanwhile: create it after all
+        Err(AxError::NotFound) => make_open_options(flags, mode)
+            .open(fs, path)
+            .map(|res| (res, true)),
+        res => res.map(|res| (res, false)),
+    }
+}
+
 /// Syscall: openat(2) - Open or create a file
+/// Relative paths are resolved from `dirfd` (AT_FDCWD for the current directory).
+/// Directories and O_PATH handles get an fd usable as the dirfd of later *at calls.
+/// Supports O_EXCL, O_CLOEXEC, O_NOFOLLOW, O_DIRECTORY, O_NONBLOCK, O_PATH and O_TMPFILE;
+/// the access mode and status flags are recorded for F_GETFL.
 /// Returns file descriptor on success, negative error on failure
-pub fn sys_openat(dirfd: isize, path: usize, flags: usize, _mode: usize) -> isize {
-    if dirfd != AT_FDCWD {
-        return linux_err_to_isize(LinuxError::EINVAL);
-    }
+pub fn sys_openat(dirfd: isize, path: usize, flags: usize, mode: usize) -> isize {
+    // 64-bit kernels always open with O_LARGEFILE
+    let flags = flags as u32 | O_LARGEFILE;
+    let mode = mode as u32;
+    let fd_flags = if flags & O_CLOEXEC != 0 { FD_CLOEXEC } else { 0 };
     
     let path_str = match load_user_cstring(path) {
         Ok(s) => s,
         Err(e) => return ax_err_to_isize(e),
     };
     
-    let fs = match get_fs_context() {
-        Ok(fs) => fs,
-        Err(e) => return ax_err_to_isize(e),
+    // Claim the fd before opening, so EMFILE leaves no file created or truncated.
+    // Every early return below drops the reservation, which frees the fd again.
+    let reserved = match ReservedFd::new() {
+        Ok(r) => r,
+        Err(e) => return linux_err_to_isize(e),
     };
     
-    let opts = make_open_options(flags as u32);
-    let file = match opts.open(&fs, &path_str) {
-        Ok(result) => match result.into_file() {
-            Ok(f) => f,
-            Err(e) => return vfs_err_to_isize(e),
-        },
-        Err(e) => return vfs_err_to_isize(e),
+    let fs = match fs_context_at(dirfd, &path_str) {
+        Ok(fs) => fs,
+        Err(e) => return linux_err_to_isize(e),
     };
     
-    fd_table::add_file(file) as isize
+    // O_PATH only resolves the path; nothing is opened for I/O.
+    // With O_NOFOLLOW the fd refers to a final symlink itself.
+    if flags & O_PATH != 0 {
+        let res = if flags & O_NOFOLLOW != 0 {
+            fs.resolve_no_follow(&path_str)
+        } else {
+            fs.resolve(&path_str)
+        };
+        let loc = match res {
+            Ok(loc) => loc,
+            Err(e) => return ax_err_to_isize(e),
+        };
+        if flags & O_DIRECTORY != 0 && !loc.is_dir() {
+            return linux_err_to_isize(LinuxError::ENOTDIR);
+        }
+        let status = flags & (O_PATH | O_LARGEFILE);
+        return reserved.install(PathFile::new(loc), status, fd_flags) as isize;
+    }
+    
+    if flags & O_TMPFILE == O_TMPFILE {
+        return match open_tmpfile(&fs, &path_str, flags, mode) {
+            Ok(f) => reserved.install(f, flags & STATUS_FLAGS, fd_flags) as isize,
+            Err(e) => linux_err_to_isize(e),
+        };
+    }
+    
+    match open_reporting_creation(&fs, &path_str, flags, mode) {
+        Ok((OpenResult::File(f), created)) => {
+            // O_NOFOLLOW left a symlink in the last component unresolved: refuse it
+            if f.location().node_type() == NodeType::Symlink {
+                return linux_err_to_isize(LinuxError::ELOOP);
+            }
+            notify_open(f.location(), created, flags);
+            reserved.install(f, flags & STATUS_FLAGS, fd_flags) as isize
+        }
+        Ok((OpenResult::Dir(loc), _)) => {
+            // Directories can only be opened read-only
+            if flags & O_ACCMODE != O_RDONLY {
+                return linux_err_to_isize(LinuxError::EISDIR);
+            }
+            reserved.install(Directory::new(loc), flags & STATUS_FLAGS, fd_flags) as isize
+        }
+        Err(e) => ax_err_to_isize(e),
+    }
+}
+
+/// Report a file an open created or truncated to inotify watchers
+fn notify_open(loc: &Location, created: bool, flags: u32) {
+    if created {
+        if let Some(parent) = loc.parent() {
+            inotify::notify_entry(&parent, loc.name(), IN_CREATE, 0, false);
+        }
+    } else if flags & O_TRUNC != 0 && flags & O_ACCMODE != O_RDONLY {
+        inotify::notify_node(loc, IN_MODIFY);
+    }
 }
 
 /// Syscall: read(2) - Read from a file
 /// Returns number of bytes read on success, negative error on failure
 pub fn sys_read(fd: i32, buf: usize, count: usize) -> isize {
-    let res = fd_table::with_file_mut(fd, |file| {
-        let slice = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, count) };
-        file.read(slice)
-    });
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
     
-    match res {
-        Some(Ok(n)) => n as isize,
-        Some(Err(_e)) => linux_err_to_isize(LinuxError::EIO),
-        None => linux_err_to_isize(LinuxError::EBADF),
+    let slice = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, count) };
+    match file.read(slice) {
+        Ok(n) => n as isize,
+        Err(e) => linux_err_to_isize(e),
     }
 }
 
 /// Syscall: write(2) - Write to a file
 /// Returns number of bytes written on success, negative error on failure
 pub fn sys_write(fd: i32, buf: usize, count: usize) -> isize {
-    let res = fd_table::with_file_mut(fd, |file| {
-        let slice = unsafe { core::slice::from_raw_parts(buf as *const u8, count) };
-        file.write(slice)
-    });
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
     
-    match res {
-        Some(Ok(n)) => n as isize,
-        Some(Err(_e)) => linux_err_to_isize(LinuxError::EIO),
-        None => linux_err_to_isize(LinuxError::EBADF),
+    let slice = unsafe { core::slice::from_raw_parts(buf as *const u8, count) };
+    match file.write(slice) {
+        Ok(n) => n as isize,
+        Err(e) => linux_err_to_isize(e),
     }
 }
 
@@ -131,62 +277,45 @@ pub fn sys_close(fd: i32) -> isize {
     }
 }
 
-/// Syscall: lseek(2) - Change file position
-/// Returns new offset on success, negative error on failure
-pub fn sys_lseek(fd: i32, offset: isize, whence: i32) -> isize {
-    let res = fd_table::with_file_mut(fd, |file| {
-        let seek_from = match whence {
-            0 => SeekFrom::Start(offset as u64),
-            1 => SeekFrom::Current(offset as i64),
-            2 => SeekFrom::End(offset as i64),
-            _ => return Err(AxError::InvalidInput),
-        };
-        
-        (&*file).seek(seek_from)
-            .map(|pos| pos as isize)
-            .map_err(|_| AxError::InvalidInput)
-    });
+/// Syscall: close_range(2) - Close all fds from `first` to `last` inclusive
+/// CLOSE_RANGE_CLOEXEC marks them close-on-exec instead of closing them.
+/// CLOSE_RANGE_UNSHARE is accepted: the fd table is never shared here.
+/// Returns 0 on success, negative error on failure
+pub fn sys_close_range(first: u32, last: u32, flags: u32) -> isize {
+    const CLOSE_RANGE_UNSHARE: u32 = 1 << 1;
+    const CLOSE_RANGE_CLOEXEC: u32 = 1 << 2;
     
-    match res {
-        Some(Ok(pos)) => pos,
-        Some(Err(_)) => linux_err_to_isize(LinuxError::EINVAL),
-        None => linux_err_to_isize(LinuxError::EBADF),
+    if flags & !(CLOSE_RANGE_UNSHARE | CLOSE_RANGE_CLOEXEC) != 0 || first > last {
+        return linux_err_to_isize(LinuxError::EINVAL);
     }
+    
+    if flags & CLOSE_RANGE_CLOEXEC != 0 {
+        fd_table::set_cloexec_range(first as usize, last as usize);
+    } else {
+        fd_table::remove_range(first as usize, last as usize);
+    }
+    0
 }
 
-/// Syscall: fstat(2) - Get file status
-/// Returns 0 on success, negative error on failure
-pub fn sys_fstat(fd: i32, statbuf: usize) -> isize {
-    let res = fd_table::with_file_mut(fd, |file| -> Result<i32, AxError> {
-        // Get current position
-        let cur_pos = (&*file).seek(SeekFrom::Current(0))
-            .map_err(|_| AxError::InvalidInput)?;
-        
-        // Get file size
-        let end_pos = (&*file).seek(SeekFrom::End(0))
-            .map_err(|_| AxError::InvalidInput)?;
-        
-        // Restore position
-        (&*file).seek(SeekFrom::Start(cur_pos))
-            .map_err(|_| AxError::InvalidInput)?;
-        
-        // Build stat struct
-        let mut st: stat = unsafe { core::mem::zeroed() };
-        st.st_mode = (S_IFREG | 0o666) as _;
-        st.st_nlink = 1;
-        st.st_size = end_pos as _;
-        st.st_blksize = 4096;
-        
-        // Write to user space
-        unsafe { (statbuf as *mut stat).write(st); }
-        
-        Ok(0)
-    });
+/// Syscall: lseek(2) - Change file position
+/// On a directory fd this repositions the getdents64 stream (rewinddir, seekdir)
+/// Returns new offset on success, negative error on failure
+pub fn sys_lseek(fd: i32, offset: isize, whence: i32) -> isize {
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
     
-    match res {
-        Some(Ok(_)) => 0,
-        Some(Err(_)) => linux_err_to_isize(LinuxError::EIO),
-        None => linux_err_to_isize(LinuxError::EBADF),
+    let seek_from = match whence {
+        0 => SeekFrom::Start(offset as u64),
+        1 => SeekFrom::Current(offset as i64),
+        2 => SeekFrom::End(offset as i64),
+        _ => return linux_err_to_isize(LinuxError::EINVAL),
+    };
+    
+    match file.object.seek(seek_from) {
+        Ok(pos) => pos as isize,
+        Err(e) => linux_err_to_isize(e),
     }
 }
 
@@ -194,18 +323,29 @@ pub fn sys_fstat(fd: i32, statbuf: usize) -> isize {
 /// Supported commands:
 /// - F_GETFD: Get file descriptor flags
 /// - F_SETFD: Set file descriptor flags
-/// - F_GETFL: Get file status flags
-/// - F_SETFL: Set file status flags  
-/// - F_DUPFD: Duplicate file descriptor
+/// - F_GETFL: Get access mode and file status flags
+/// - F_SETFL: Set file status flags; only O_APPEND, O_NONBLOCK, O_ASYNC,
+///   O_DIRECT and O_NOATIME can be changed, other bits in `arg` are ignored
+/// - F_DUPFD: Duplicate file descriptor onto the lowest fd >= arg
+/// - F_DUPFD_CLOEXEC: Same as F_DUPFD, with FD_CLOEXEC set on the new fd
 pub fn sys_fcntl(fd: i32, cmd: i32, arg: usize) -> isize {
     const F_GETFD: i32 = 1;
     const F_SETFD: i32 = 2;
     const F_GETFL: i32 = 3;
     const F_SETFL: i32 = 4;
     const F_DUPFD: i32 = 0;
+    const F_DUPFD_CLOEXEC: i32 = 1030;
+    const F_GETLK: i32 = 5;
+    const F_SETLK: i32 = 6;
+    const F_SETLKW: i32 = 7;
+    const F_OFD_GETLK: i32 = 36;
+    const F_OFD_SETLK: i32 = 37;
+    const F_OFD_SETLKW: i32 = 38;
+    const F_ADD_SEALS: i32 = 1033;
+    const F_GET_SEALS: i32 = 1034;
     
     // Verify fd is valid first
-    if fd_table::get_fd_flags(fd).is_none() && cmd != F_DUPFD {
+    if fd_table::get_fd_flags(fd).is_none() {
         return linux_err_to_isize(LinuxError::EBADF);
     }
     
@@ -218,8 +358,8 @@ pub fn sys_fcntl(fd: i32, cmd: i32, arg: usize) -> isize {
             }
         }
         F_SETFD => {
-            // Set file descriptor flags
-            if fd_table::set_fd_flags(fd, arg as u32) {
+            // Set file descriptor flags (FD_CLOEXEC is the only one defined)
+            if fd_table::set_fd_flags(fd, arg as u32 & FD_CLOEXEC) {
                 0
             } else {
                 linux_err_to_isize(LinuxError::EBADF)
@@ -233,40 +373,297 @@ pub fn sys_fcntl(fd: i32, cmd: i32, arg: usize) -> isize {
             }
         }
         F_SETFL => {
-            // Set file status flags
+            // O_PATH descriptions have no status flags to change
+            if fd_table::get_file_flags(fd).is_some_and(|flags| flags & O_PATH != 0) {
+                return linux_err_to_isize(LinuxError::EBADF);
+            }
             if fd_table::set_file_flags(fd, arg as u32) {
                 0
             } else {
                 linux_err_to_isize(LinuxError::EBADF)
             }
         }
-        F_DUPFD => {
-            // Duplicate file descriptor (not fully implemented due to File not being Clone-able)
-            // Would need Arc<File> or similar in production
-            ENOSYS
+        F_DUPFD | F_DUPFD_CLOEXEC => {
+            // The new fd shares the open file description (offset, status flags)
+            match fd_table::dup_fd(fd, arg, cmd == F_DUPFD_CLOEXEC) {
+                Ok(new_fd) => new_fd as isize,
+                Err(e) => linux_err_to_isize(e),
+            }
+        }
+        F_GETLK | F_OFD_GETLK => {
+            let res = fd_table::get_file(fd)
+                .ok_or(LinuxError::EBADF)
+                .and_then(|file| lock::get_lock(&file, cmd == F_OFD_GETLK, arg));
+            res.unwrap_or_else(linux_err_to_isize)
+        }
+        F_SETLK | F_SETLKW | F_OFD_SETLK | F_OFD_SETLKW => {
+            // Process-owned (POSIX) or open-file-description-owned (OFD) byte-range locks
+            let ofd = matches!(cmd, F_OFD_SETLK | F_OFD_SETLKW);
+            let wait = matches!(cmd, F_SETLKW | F_OFD_SETLKW);
+            let res = fd_table::get_file(fd)
+                .ok_or(LinuxError::EBADF)
+                .and_then(|file| lock::set_lock(&file, ofd, wait, arg));
+            res.unwrap_or_else(linux_err_to_isize)
+        }
+        F_ADD_SEALS | F_GET_SEALS => {
+            // Only memfds can be sealed
+            let res = fd_table::get_file(fd).ok_or(LinuxError::EBADF).and_then(|file| {
+                let memfd = file.downcast_ref::<MemFd>().ok_or(LinuxError::EINVAL)?;
+                if cmd == F_GET_SEALS {
+                    return Ok(memfd.seals() as isize);
+                }
+                if file.check_writable().is_err() {
+                    return Err(LinuxError::EPERM);
+                }
+                memfd.add_seals(arg as u32).map(|()| 0)
+            });
+            res.unwrap_or_else(linux_err_to_isize)
         }
-        _ => ENOSYS,
+        _ => linux_err_to_isize(LinuxError::EINVAL),
+    }
+}
+
+/// Syscall: dup(2) - Duplicate a file descriptor
+/// Returns the lowest free fd, which shares the open file description of `fd`
+pub fn sys_dup(fd: i32) -> isize {
+    match fd_table::dup_fd(fd, 0, false) {
+        Ok(new_fd) => new_fd as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: dup3(2) - Duplicate a file descriptor onto a specific fd
+/// `new_fd` is closed first if open; only O_CLOEXEC is accepted in `flags`
+pub fn sys_dup3(old_fd: i32, new_fd: i32, flags: u32) -> isize {
+    if flags & !O_CLOEXEC != 0 || old_fd == new_fd {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    
+    match fd_table::dup_fd_to(old_fd, new_fd, flags & O_CLOEXEC != 0) {
+        Ok(fd) => fd as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Check a length/offset argument of the size syscalls
+fn check_len(len: i64) -> Result<u64, LinuxError> {
+    if len < 0 {
+        Err(LinuxError::EINVAL)
+    } else {
+        Ok(len as u64)
     }
 }
 
-/// Helper: Convert AxError to LinuxError and then to isize
-fn ax_err_to_isize(err: AxError) -> isize {
-    let linux_err = LinuxError::from(err);
-    linux_err_to_isize(linux_err)
+/// Overwrite `[offset, offset + len)` with zeros
+fn zero_range(file: &OpenFile, offset: u64, len: u64) -> Result<(), LinuxError> {
+    const ZEROS: [u8; 4096] = [0; 4096];
+    
+    let end = offset + len;
+    let mut pos = offset;
+    while pos < end {
+        let chunk = (end - pos).min(ZEROS.len() as u64) as usize;
+        let n = file
+            .object
+            .write_at(&ZEROS[..chunk], pos, file.status_flags())?;
+        if n == 0 {
+            return Err(LinuxError::EIO);
+        }
+        pos += n as u64;
+    }
+    Ok(())
 }
 
-/// Helper: Convert VfsError to LinuxError and then to isize
-fn vfs_err_to_isize(err: VfsError) -> isize {
-    let linux_err = match err {
-        VfsError::NotFound => LinuxError::ENOENT,
-        VfsError::PermissionDenied => LinuxError::EACCES,
-        VfsError::IsADirectory => LinuxError::EISDIR,
-        VfsError::NotADirectory => LinuxError::ENOTDIR,
-        VfsError::AlreadyExists => LinuxError::EEXIST,
-        VfsError::InvalidInput => LinuxError::EINVAL,
-        VfsError::FilesystemLoop => LinuxError::ELOOP,
-        VfsError::ReadOnlyFilesystem => LinuxError::EROFS,
-        _ => LinuxError::EIO,
+/// Syscall: ftruncate(2) - Set the size of an open file
+/// Growing the file fills the new range with zeros
+/// Returns 0 on success, negative error on failure
+pub fn sys_ftruncate(fd: i32, length: i64) -> isize {
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
     };
-    linux_err_to_isize(linux_err)
+    
+    let res = check_len(length).and_then(|len| {
+        // O_PATH handles are not open for I/O at all
+        if file.status_flags() & O_PATH != 0 {
+            return Err(LinuxError::EBADF);
+        }
+        // Only descriptions open for writing can truncate; objects without a size refuse with EINVAL
+        if file.status_flags() & O_ACCMODE == O_RDONLY {
+            return Err(LinuxError::EINVAL);
+        }
+        file.object.truncate(len)?;
+        if let Some(loc) = file.location() {
+            inotify::notify_node(loc, IN_MODIFY);
+        }
+        Ok(())
+    });
+    
+    match res {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: truncate(2) - Set the size of a file by path
+/// Returns 0 on success, negative error on failure
+pub fn sys_truncate(path: usize, length: i64) -> isize {
+    let res = (|| -> Result<(), LinuxError> {
+        let len = check_len(length)?;
+        let path_str = load_user_cstring(path).map_err(ax_err_to_linux)?;
+        let fs = fs_context_at(AT_FDCWD, &path_str)?;
+        
+        let mut opts = OpenOptions::new();
+        opts.write(true);
+        let file = match opts.open(&fs, &path_str).map_err(ax_err_to_linux)? {
+            OpenResult::File(f) => f,
+            OpenResult::Dir(_) => return Err(LinuxError::EISDIR),
+        };
+        file.set_len(len).map_err(ax_err_to_linux)?;
+        inotify::notify_node(file.location(), IN_MODIFY);
+        Ok(())
+    })();
+    
+    match res {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: fallocate(2) - Manipulate the allocated space of a file
+/// Supported modes:
+/// - 0: make sure `[offset, offset + len)` is backed, growing the file if needed
+/// - FALLOC_FL_KEEP_SIZE: same, but never change the file size
+/// - FALLOC_FL_PUNCH_HOLE | FALLOC_FL_KEEP_SIZE: make the range read back as zeros
+/// - FALLOC_FL_ZERO_RANGE: zero the range, growing the file unless KEEP_SIZE
+/// Our backends have no sparse-file support, so holes are written as zeros.
+/// Works on any regular file, memfds included; the fd must be open for writing.
+/// Returns 0 on success, negative error on failure
+pub fn sys_fallocate(fd: i32, mode: u32, offset: i64, len: i64) -> isize {
+    const FALLOC_FL_KEEP_SIZE: u32 = 0x01;
+    const FALLOC_FL_PUNCH_HOLE: u32 = 0x02;
+    const FALLOC_FL_ZERO_RANGE: u32 = 0x10;
+    
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    
+    let res = (|| -> Result<(), LinuxError> {
+        if offset < 0 || len <= 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let end = offset.checked_add(len).ok_or(LinuxError::EFBIG)? as u64;
+        let offset = offset as u64;
+        
+        file.check_writable()?;
+        // Any regular file works (disk files, memfds); the object enforces its own limits
+        let meta = file.metadata()?;
+        match meta.node_type {
+            NodeType::RegularFile => {}
+            NodeType::Directory => return Err(LinuxError::EISDIR),
+            NodeType::Fifo => return Err(LinuxError::ESPIPE),
+            _ => return Err(LinuxError::ENODEV),
+        }
+        let size = meta.size;
+        let keep_size = mode & FALLOC_FL_KEEP_SIZE != 0;
+        
+        match mode & !FALLOC_FL_KEEP_SIZE {
+            0 => {
+                if !keep_size && end > size {
+                    file.object.truncate(end)?;
+                }
+                Ok(())
+            }
+            FALLOC_FL_PUNCH_HOLE => {
+                // Punching a hole must never change the size
+                if !keep_size {
+                    return Err(LinuxError::EINVAL);
+                }
+                if offset < size {
+                    zero_range(&file, offset, end.min(size) - offset)?;
+                }
+                Ok(())
+            }
+            FALLOC_FL_ZERO_RANGE => {
+                let zero_end = if keep_size { end.min(size) } else { end };
+                if offset < zero_end {
+                    zero_range(&file, offset, zero_end - offset)?;
+                }
+                Ok(())
+            }
+            _ => Err(LinuxError::EOPNOTSUPP),
+        }?;
+        if let Some(loc) = file.location() {
+            inotify::notify_node(loc, IN_MODIFY);
+        }
+        Ok(())
+    })();
+    
+    match res {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Flush file data (and metadata unless `data_only`) of `fd`
+pub(super) fn sync_fd(fd: i32, data_only: bool) -> Result<(), LinuxError> {
+    let file = fd_table::get_file(fd).ok_or(LinuxError::EBADF)?;
+    file.object.sync(data_only)
+}
+
+/// Syscall: fsync(2) - Flush file data and metadata to disk
+/// Returns 0 on success, negative error on failure
+pub fn sys_fsync(fd: i32) -> isize {
+    match sync_fd(fd, false) {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: fdatasync(2) - Flush file data, and only the metadata needed to read it back
+/// Returns 0 on success, negative error on failure
+pub fn sys_fdatasync(fd: i32) -> isize {
+    match sync_fd(fd, true) {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: sync(2) - Flush every open file and the root filesystem
+/// Always succeeds, as on Linux
+pub fn sys_sync() -> isize {
+    for file in fd_table::open_files() {
+        if let Some(f) = file.downcast_ref::<File>() {
+            let _ = f.sync(false);
+        }
+    }
+    if let Ok(fs) = get_fs_context() {
+        let _ = fs.root_dir().filesystem().flush();
+    }
+    0
+}
+
+/// Syscall: syncfs(2) - Flush the filesystem containing `fd`
+/// Open files on that filesystem are flushed first, then the filesystem itself
+/// Returns 0 on success, negative error on failure
+pub fn sys_syncfs(fd: i32) -> isize {
+    let res = (|| -> Result<(), LinuxError> {
+        let file = fd_table::get_file(fd).ok_or(LinuxError::EBADF)?;
+        let loc = file.location().ok_or(LinuxError::EINVAL)?;
+        let device = loc.metadata().map_err(ax_err_to_linux)?.device;
+        
+        for other in fd_table::open_files() {
+            if let Some(f) = other.downcast_ref::<File>() {
+                if matches!(f.location().metadata(), Ok(m) if m.device == device) {
+                    f.sync(false).map_err(ax_err_to_linux)?;
+                }
+            }
+        }
+        loc.filesystem().flush().map_err(ax_err_to_linux)
+    })();
+    
+    match res {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
 }
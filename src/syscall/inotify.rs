@@ -0,0 +1,418 @@
+/// Filesystem change notifications: inotify_init1, inotify_add_watch, inotify_rm_watch
+///
+/// Watches are keyed by the (device, inode) of the watched node. The syscall
+/// layer reports changes through `notify_entry` (a name in a directory
+/// changed), `notify_self` (a node itself changed) and `notify_node` (both),
+/// which queue `inotify_event` records on every instance watching the node.
+
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::{any::Any, task::Context};
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::{Location, Metadata};
+use axpoll::{IoEvents, PollSet};
+use axtask::WaitQueue;
+use linux_raw_sys::general::{
+    IN_ALL_EVENTS, IN_DELETE_SELF, IN_DONT_FOLLOW, IN_EXCL_UNLINK, IN_IGNORED, IN_ISDIR,
+    IN_MASK_ADD, IN_MASK_CREATE, IN_ONESHOT, IN_ONLYDIR, IN_Q_OVERFLOW, O_CLOEXEC, O_NONBLOCK,
+    O_RDONLY,
+};
+
+use super::{
+    ax_err_to_linux,
+    fd_table::{self, SpinLock, FD_CLOEXEC, FD_NONBLOCK},
+    file::{anon_inode_metadata, FileLike},
+    fs::load_user_cstring,
+    linux_err_to_isize,
+    path::{fs_context_at, AT_FDCWD},
+};
+
+/// ioctl: number of bytes ready to read
+const FIONREAD: u32 = 0x541B;
+
+/// Size of the fixed part of an inotify_event record (wd, mask, cookie, len)
+const EVENT_HEADER: usize = 16;
+
+/// Most events an instance queues before reporting IN_Q_OVERFLOW (max_queued_events)
+const MAX_QUEUED: usize = 16384;
+
+/// Flags inotify_add_watch accepts on top of the event bits
+const WATCH_FLAGS: u32 =
+    IN_DONT_FOLLOW | IN_EXCL_UNLINK | IN_MASK_ADD | IN_MASK_CREATE | IN_ONESHOT | IN_ONLYDIR;
+
+/// Every live instance, so changes can be matched against their watches
+static INSTANCES: SpinLock<Vec<Weak<InotifyShared>>> = SpinLock::new(Vec::new());
+
+/// Source of cookies that tie IN_MOVED_FROM to IN_MOVED_TO
+static NEXT_COOKIE: SpinLock<u32> = SpinLock::new(1);
+
+struct Watch {
+    /// (device, inode) of the watched node
+    node: (u64, u64),
+    mask: u32,
+}
+
+#[derive(PartialEq)]
+struct Event {
+    wd: i32,
+    mask: u32,
+    cookie: u32,
+    name: Option<String>,
+}
+
+impl Event {
+    /// Length of the name field: NUL-terminated and padded to a whole header
+    fn name_len(&self) -> usize {
+        self.name
+            .as_ref()
+            .map_or(0, |name| (name.len() + 1).next_multiple_of(EVENT_HEADER))
+    }
+
+    fn record_len(&self) -> usize {
+        EVENT_HEADER + self.name_len()
+    }
+
+    /// Serialize as a struct inotify_event
+    fn write_to(&self, buf: &mut [u8]) {
+        let len = self.name_len();
+        buf[0..4].copy_from_slice(&self.wd.to_ne_bytes());
+        buf[4..8].copy_from_slice(&self.mask.to_ne_bytes());
+        buf[8..12].copy_from_slice(&self.cookie.to_ne_bytes());
+        buf[12..16].copy_from_slice(&(len as u32).to_ne_bytes());
+        let name = &mut buf[EVENT_HEADER..EVENT_HEADER + len];
+        name.fill(0);
+        if let Some(s) = &self.name {
+            name[..s.len()].copy_from_slice(s.as_bytes());
+        }
+    }
+}
+
+struct InotifyState {
+    watches: BTreeMap<i32, Watch>,
+    next_wd: i32,
+    queue: VecDeque<Event>,
+}
+
+impl InotifyState {
+    /// Queue an event, merging it into an identical last one and capping the queue as Linux does
+    fn push(&mut self, event: Event) {
+        if self.queue.back() == Some(&event) {
+            return;
+        }
+        if self.queue.len() >= MAX_QUEUED {
+            let overflow = Event {
+                wd: -1,
+                mask: IN_Q_OVERFLOW,
+                cookie: 0,
+                name: None,
+            };
+            if self.queue.back() != Some(&overflow) {
+                self.queue.push_back(overflow);
+            }
+            return;
+        }
+        self.queue.push_back(event);
+    }
+
+    /// Drop watch `wd`, telling the reader with IN_IGNORED
+    fn remove_watch(&mut self, wd: i32) -> bool {
+        if self.watches.remove(&wd).is_none() {
+            return false;
+        }
+        self.push(Event {
+            wd,
+            mask: IN_IGNORED,
+            cookie: 0,
+            name: None,
+        });
+        true
+    }
+
+    fn queued_bytes(&self) -> usize {
+        self.queue.iter().map(Event::record_len).sum()
+    }
+}
+
+struct InotifyShared {
+    state: SpinLock<InotifyState>,
+    /// Woken when events are queued
+    ready: PollSet,
+    /// Blocked readers, woken along with `ready`
+    readers: WaitQueue,
+}
+
+impl InotifyShared {
+    /// Events were queued
+    fn wake(&self) {
+        self.ready.wake();
+        self.readers.notify_all(false);
+    }
+
+    /// Queue `mask` for every watch on `node` interested in it
+    fn deliver(&self, node: (u64, u64), mask: u32, cookie: u32, name: Option<&str>) {
+        let mut state = self.state.lock();
+        let matched: Vec<(i32, bool)> = state
+            .watches
+            .iter()
+            .filter(|(_, w)| w.node == node && w.mask & mask & IN_ALL_EVENTS != 0)
+            .map(|(&wd, w)| (wd, w.mask & IN_ONESHOT != 0))
+            .collect();
+        if matched.is_empty() {
+            return;
+        }
+        for (wd, oneshot) in matched {
+            state.push(Event {
+                wd,
+                mask,
+                cookie,
+                name: name.map(String::from),
+            });
+            // A watch goes away after its one event, or with the node it watches
+            if oneshot || mask & IN_DELETE_SELF != 0 {
+                state.remove_watch(wd);
+            }
+        }
+        drop(state);
+        self.wake();
+    }
+}
+
+/// An inotify instance
+pub struct Inotify {
+    shared: Arc<InotifyShared>,
+}
+
+/// (device, inode) identifying `loc` across lookups
+fn node_key(loc: &Location) -> Option<(u64, u64)> {
+    let meta = loc.metadata().ok()?;
+    Some((meta.device, meta.inode))
+}
+
+/// Queue an event on every instance watching `loc`
+fn notify(loc: &Location, mask: u32, cookie: u32, name: Option<&str>) {
+    let instances: Vec<Arc<InotifyShared>> = {
+        let mut instances = INSTANCES.lock();
+        instances.retain(|w| w.strong_count() > 0);
+        instances.iter().filter_map(Weak::upgrade).collect()
+    };
+    // Most of the time nobody is watching; skip the metadata lookup then
+    if instances.is_empty() {
+        return;
+    }
+    let Some(node) = node_key(loc) else {
+        return;
+    };
+    for instance in instances {
+        instance.deliver(node, mask, cookie, name);
+    }
+}
+
+/// Report a change to entry `name` of directory `dir` (IN_CREATE, IN_DELETE, IN_MOVED_*).
+/// `is_dir` tells whether the entry is a directory, for IN_ISDIR.
+pub fn notify_entry(dir: &Location, name: &str, mask: u32, cookie: u32, is_dir: bool) {
+    let mask = if is_dir { mask | IN_ISDIR } else { mask };
+    notify(dir, mask, cookie, Some(name));
+}
+
+/// Report a change to node `loc` itself (IN_DELETE_SELF, IN_MOVE_SELF, IN_ATTRIB)
+pub fn notify_self(loc: &Location, mask: u32) {
+    let mask = if loc.is_dir() { mask | IN_ISDIR } else { mask };
+    notify(loc, mask, 0, None);
+}
+
+/// Report a change to the contents of `loc` (IN_MODIFY, IN_CLOSE_*) to watches on
+/// the node itself and on the directory it was reached through
+pub fn notify_node(loc: &Location, mask: u32) {
+    notify_self(loc, mask);
+    if let Some(parent) = loc.parent() {
+        notify_entry(&parent, loc.name(), mask, 0, loc.is_dir());
+    }
+}
+
+/// Get a fresh cookie for a rename's IN_MOVED_FROM/IN_MOVED_TO pair
+pub fn next_cookie() -> u32 {
+    let mut next = NEXT_COOKIE.lock();
+    let cookie = *next;
+    *next = next.wrapping_add(1).max(1);
+    cookie
+}
+
+impl FileLike for Inotify {
+    /// Take as many whole events as fit in `buf`; EINVAL if not even the first one does
+    fn read(&self, buf: &mut [u8], flags: u32) -> Result<usize, LinuxError> {
+        loop {
+            {
+                let mut state = self.shared.state.lock();
+                let mut done = 0;
+                while let Some(event) = state.queue.front() {
+                    let len = event.record_len();
+                    if done + len > buf.len() {
+                        if done == 0 {
+                            return Err(LinuxError::EINVAL);
+                        }
+                        break;
+                    }
+                    event.write_to(&mut buf[done..done + len]);
+                    done += len;
+                    state.queue.pop_front();
+                }
+                if done > 0 {
+                    return Ok(done);
+                }
+            }
+            if flags & FD_NONBLOCK != 0 {
+                return Err(LinuxError::EAGAIN);
+            }
+            self.shared
+                .readers
+                .wait_until(|| !self.shared.state.lock().queue.is_empty());
+        }
+    }
+
+    fn write(&self, _buf: &[u8], _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EINVAL)
+    }
+
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        Ok(anon_inode_metadata(Arc::as_ptr(&self.shared) as u64))
+    }
+
+    /// FIONREAD reports the bytes queued
+    fn ioctl(&self, cmd: u32, arg: usize) -> Result<isize, LinuxError> {
+        match cmd {
+            FIONREAD => {
+                if arg == 0 {
+                    return Err(LinuxError::EFAULT);
+                }
+                let bytes = self.shared.state.lock().queued_bytes();
+                unsafe { (arg as *mut i32).write_unaligned(bytes as i32) };
+                Ok(0)
+            }
+            _ => Err(LinuxError::ENOTTY),
+        }
+    }
+
+    /// Readable while events are queued
+    fn poll(&self) -> IoEvents {
+        if self.shared.state.lock().queue.is_empty() {
+            IoEvents::empty()
+        } else {
+            IoEvents::IN
+        }
+    }
+
+    fn register(&self, context: &mut Context<'_>, _events: IoEvents) {
+        self.shared.ready.register(context.waker());
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Syscall: inotify_init1(2) - Create an inotify instance
+/// Supported flags: IN_NONBLOCK, IN_CLOEXEC (the O_NONBLOCK and O_CLOEXEC values)
+/// Returns the new fd on success, negative error on failure
+pub fn sys_inotify_init1(flags: u32) -> isize {
+    if flags & !(O_NONBLOCK | O_CLOEXEC) != 0 {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    let shared = Arc::new(InotifyShared {
+        state: SpinLock::new(InotifyState {
+            watches: BTreeMap::new(),
+            next_wd: 1,
+            queue: VecDeque::new(),
+        }),
+        ready: PollSet::new(),
+        readers: WaitQueue::new(),
+    });
+    INSTANCES.lock().push(Arc::downgrade(&shared));
+
+    let fd_flags = if flags & O_CLOEXEC != 0 { FD_CLOEXEC } else { 0 };
+    match fd_table::add_file(Inotify { shared }, O_RDONLY | (flags & O_NONBLOCK), fd_flags) {
+        Ok(fd) => fd as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: inotify_add_watch(2) - Watch a file or directory for the events in `mask`
+/// Watching a node that is already watched updates that watch (or, with
+/// IN_MASK_ADD, extends it; with IN_MASK_CREATE it is EEXIST).
+/// Returns the watch descriptor on success, negative error on failure
+pub fn sys_inotify_add_watch(fd: i32, path: usize, mask: u32) -> isize {
+    let res = (|| -> Result<i32, LinuxError> {
+        let file = fd_table::get_file(fd).ok_or(LinuxError::EBADF)?;
+        let inotify = file.downcast_ref::<Inotify>().ok_or(LinuxError::EINVAL)?;
+        if mask & !(IN_ALL_EVENTS | WATCH_FLAGS) != 0
+            || mask & IN_ALL_EVENTS == 0
+            || mask & (IN_MASK_ADD | IN_MASK_CREATE) == (IN_MASK_ADD | IN_MASK_CREATE)
+        {
+            return Err(LinuxError::EINVAL);
+        }
+
+        let path = load_user_cstring(path).map_err(ax_err_to_linux)?;
+        let fs = fs_context_at(AT_FDCWD, &path)?;
+        let loc = if mask & IN_DONT_FOLLOW != 0 {
+            fs.resolve_no_follow(&path)
+        } else {
+            fs.resolve(&path)
+        }
+        .map_err(ax_err_to_linux)?;
+        if mask & IN_ONLYDIR != 0 && !loc.is_dir() {
+            return Err(LinuxError::ENOTDIR);
+        }
+        let node = node_key(&loc).ok_or(LinuxError::ENOENT)?;
+
+        let mut state = inotify.shared.state.lock();
+        let existing = state.watches.iter_mut().find(|(_, w)| w.node == node);
+        if let Some((&wd, watch)) = existing {
+            if mask & IN_MASK_CREATE != 0 {
+                return Err(LinuxError::EEXIST);
+            }
+            if mask & IN_MASK_ADD != 0 {
+                watch.mask |= mask & !IN_MASK_ADD;
+            } else {
+                watch.mask = mask;
+            }
+            return Ok(wd);
+        }
+        let wd = state.next_wd;
+        state.next_wd += 1;
+        state.watches.insert(
+            wd,
+            Watch {
+                node,
+                mask: mask & !(IN_MASK_ADD | IN_MASK_CREATE),
+            },
+        );
+        Ok(wd)
+    })();
+
+    match res {
+        Ok(wd) => wd as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: inotify_rm_watch(2) - Remove a watch; an IN_IGNORED event is queued for it
+/// Returns 0 on success, negative error on failure (EINVAL for an unknown `wd`)
+pub fn sys_inotify_rm_watch(fd: i32, wd: i32) -> isize {
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    let inotify = match file.downcast_ref::<Inotify>() {
+        Some(i) => i,
+        None => return linux_err_to_isize(LinuxError::EINVAL),
+    };
+    if !inotify.shared.state.lock().remove_watch(wd) {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    inotify.shared.wake();
+    0
+}
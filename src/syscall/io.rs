@@ -0,0 +1,289 @@
+/// Positional and vectored I/O: pread64, pwrite64, readv, writev,
+/// preadv, pwritev, preadv2, pwritev2
+///
+/// readv/writev work on every fd type; positional variants need a seekable
+/// file and never move the fd offset. Vectored variants follow the Linux
+/// partial-transfer rules: buffers are processed in order, a short transfer
+/// ends the call, and an error after some bytes were moved is reported as
+/// that byte count instead.
+
+use axerrno::LinuxError;
+use linux_raw_sys::general::{
+    iovec, O_APPEND, O_NONBLOCK, RWF_APPEND, RWF_DSYNC, RWF_HIPRI, RWF_NOWAIT, RWF_SYNC,
+};
+
+use super::{
+    fd_table::{self, OpenFile},
+    fs::sync_fd,
+    linux_err_to_isize,
+};
+
+/// Maximum number of iovecs accepted in one call (UIO_MAXIOV)
+const IOV_MAX: usize = 1024;
+
+/// Flags preadv2/pwritev2 understand
+const RWF_SUPPORTED: u32 = RWF_HIPRI | RWF_DSYNC | RWF_SYNC | RWF_NOWAIT | RWF_APPEND;
+
+/// Load and validate a user iovec array
+fn load_iovecs<'a>(iov: usize, iovcnt: i32) -> Result<&'a [iovec], LinuxError> {
+    if iovcnt < 0 || iovcnt as usize > IOV_MAX {
+        return Err(LinuxError::EINVAL);
+    }
+    if iovcnt == 0 {
+        return Ok(&[]);
+    }
+    if iov == 0 {
+        return Err(LinuxError::EFAULT);
+    }
+    
+    let iovs = unsafe { core::slice::from_raw_parts(iov as *const iovec, iovcnt as usize) };
+    
+    // The total length must fit in ssize_t
+    let mut total: usize = 0;
+    for v in iovs {
+        total = total
+            .checked_add(v.iov_len as usize)
+            .filter(|&t| t <= isize::MAX as usize)
+            .ok_or(LinuxError::EINVAL)?;
+    }
+    Ok(iovs)
+}
+
+/// Run `op` over each iovec in order, passing the bytes already transferred
+fn transfer_iovecs<F>(iovs: &[iovec], mut op: F) -> isize
+where
+    F: FnMut(&iovec, u64) -> Result<usize, LinuxError>,
+{
+    let mut total: usize = 0;
+    for v in iovs {
+        if v.iov_len == 0 {
+            continue;
+        }
+        match op(v, total as u64) {
+            Ok(n) => {
+                total += n;
+                if n < v.iov_len as usize {
+                    break;
+                }
+            }
+            Err(e) if total == 0 => return linux_err_to_isize(e),
+            Err(_) => break,
+        }
+    }
+    total as isize
+}
+
+/// Status flags a preadv2/pwritev2 call adds for itself.
+/// Positional I/O only reaches regular files, which never block, so RWF_NOWAIT
+/// matters for the fd-offset form only.
+fn rwf_status_flags(flags: u32) -> u32 {
+    let mut status = 0;
+    if flags & RWF_NOWAIT != 0 {
+        status |= O_NONBLOCK;
+    }
+    if flags & RWF_APPEND != 0 {
+        status |= O_APPEND;
+    }
+    status
+}
+
+fn read_iov(
+    file: &OpenFile,
+    v: &iovec,
+    offset: Option<u64>,
+    extra: u32,
+) -> Result<usize, LinuxError> {
+    let buf = unsafe { core::slice::from_raw_parts_mut(v.iov_base as *mut u8, v.iov_len as usize) };
+    match offset {
+        Some(off) => file.read_at(buf, off),
+        None => file.read_with_flags(buf, extra),
+    }
+}
+
+fn write_iov(
+    file: &OpenFile,
+    v: &iovec,
+    offset: Option<u64>,
+    extra: u32,
+) -> Result<usize, LinuxError> {
+    let buf = unsafe { core::slice::from_raw_parts(v.iov_base as *const u8, v.iov_len as usize) };
+    match offset {
+        Some(off) => file.write_at(buf, off),
+        None => file.write_with_flags(buf, extra),
+    }
+}
+
+/// Shared implementation of the vectored reads.
+/// `offset` of None reads at (and advances) the fd offset; `extra` holds
+/// status flags added for this call only.
+fn do_readv(fd: i32, iov: usize, iovcnt: i32, offset: Option<u64>, extra: u32) -> isize {
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    if let (Some(_), Err(e)) = (offset, file.check_positional()) {
+        return linux_err_to_isize(e);
+    }
+    let iovs = match load_iovecs(iov, iovcnt) {
+        Ok(v) => v,
+        Err(e) => return linux_err_to_isize(e),
+    };
+    
+    transfer_iovecs(iovs, |v, done| {
+        read_iov(&file, v, offset.map(|off| off + done), extra)
+    })
+}
+
+/// Shared implementation of the vectored writes.
+/// `offset` of None writes at (and advances) the fd offset; `extra` holds
+/// status flags added for this call only. A positional write with O_APPEND
+/// in `extra` goes to the end of the file instead of `offset`.
+fn do_writev(fd: i32, iov: usize, iovcnt: i32, offset: Option<u64>, extra: u32) -> isize {
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    if let (Some(_), Err(e)) = (offset, file.check_positional()) {
+        return linux_err_to_isize(e);
+    }
+    let iovs = match load_iovecs(iov, iovcnt) {
+        Ok(v) => v,
+        Err(e) => return linux_err_to_isize(e),
+    };
+    let offset = match offset {
+        Some(_) if extra & O_APPEND != 0 => match file.metadata() {
+            Ok(meta) => Some(meta.size),
+            Err(e) => return linux_err_to_isize(e),
+        },
+        _ => offset,
+    };
+    
+    transfer_iovecs(iovs, |v, done| {
+        write_iov(&file, v, offset.map(|off| off + done), extra)
+    })
+}
+
+/// Check the offset argument of the positional syscalls
+fn check_offset(offset: i64) -> Result<u64, LinuxError> {
+    if offset < 0 {
+        Err(LinuxError::EINVAL)
+    } else {
+        Ok(offset as u64)
+    }
+}
+
+/// Syscall: pread64(2) - Read from a file at a given offset
+/// The fd offset is left unchanged
+/// Returns number of bytes read on success, negative error on failure
+pub fn sys_pread64(fd: i32, buf: usize, count: usize, offset: i64) -> isize {
+    let desc = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    if let Err(e) = desc.check_readable().and_then(|_| desc.check_positional()) {
+        return linux_err_to_isize(e);
+    }
+    let offset = match check_offset(offset) {
+        Ok(off) => off,
+        Err(e) => return linux_err_to_isize(e),
+    };
+    
+    let slice = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, count) };
+    match desc.read_at(slice, offset) {
+        Ok(n) => n as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: pwrite64(2) - Write to a file at a given offset
+/// The fd offset is left unchanged
+/// Returns number of bytes written on success, negative error on failure
+pub fn sys_pwrite64(fd: i32, buf: usize, count: usize, offset: i64) -> isize {
+    let desc = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    if let Err(e) = desc.check_writable().and_then(|_| desc.check_positional()) {
+        return linux_err_to_isize(e);
+    }
+    let offset = match check_offset(offset) {
+        Ok(off) => off,
+        Err(e) => return linux_err_to_isize(e),
+    };
+    
+    let slice = unsafe { core::slice::from_raw_parts(buf as *const u8, count) };
+    match desc.write_at(slice, offset) {
+        Ok(n) => n as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: readv(2) - Read into multiple buffers
+pub fn sys_readv(fd: i32, iov: usize, iovcnt: i32) -> isize {
+    do_readv(fd, iov, iovcnt, None, 0)
+}
+
+/// Syscall: writev(2) - Write from multiple buffers
+pub fn sys_writev(fd: i32, iov: usize, iovcnt: i32) -> isize {
+    do_writev(fd, iov, iovcnt, None, 0)
+}
+
+/// Syscall: preadv(2) - Read into multiple buffers at a given offset
+pub fn sys_preadv(fd: i32, iov: usize, iovcnt: i32, offset: i64) -> isize {
+    match check_offset(offset) {
+        Ok(off) => do_readv(fd, iov, iovcnt, Some(off), 0),
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: pwritev(2) - Write from multiple buffers at a given offset
+pub fn sys_pwritev(fd: i32, iov: usize, iovcnt: i32, offset: i64) -> isize {
+    match check_offset(offset) {
+        Ok(off) => do_writev(fd, iov, iovcnt, Some(off), 0),
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: preadv2(2) - preadv with per-call flags
+/// An offset of -1 reads at the current fd offset, like readv.
+/// RWF_NOWAIT fails with EAGAIN instead of waiting for data, as O_NONBLOCK would.
+pub fn sys_preadv2(fd: i32, iov: usize, iovcnt: i32, offset: i64, flags: u32) -> isize {
+    if flags & !RWF_SUPPORTED != 0 {
+        return linux_err_to_isize(LinuxError::EOPNOTSUPP);
+    }
+    if offset == -1 {
+        return do_readv(fd, iov, iovcnt, None, rwf_status_flags(flags));
+    }
+    match check_offset(offset) {
+        Ok(off) => do_readv(fd, iov, iovcnt, Some(off), rwf_status_flags(flags)),
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: pwritev2(2) - pwritev with per-call flags
+/// An offset of -1 writes at the current fd offset, like writev.
+/// RWF_DSYNC/RWF_SYNC flush the written data as O_DSYNC/O_SYNC would.
+/// RWF_APPEND writes at the end of the file, as O_APPEND would; the fd
+/// offset only moves when `offset` is -1.
+/// RWF_NOWAIT fails with EAGAIN instead of waiting for room, as O_NONBLOCK would.
+pub fn sys_pwritev2(fd: i32, iov: usize, iovcnt: i32, offset: i64, flags: u32) -> isize {
+    if flags & !RWF_SUPPORTED != 0 {
+        return linux_err_to_isize(LinuxError::EOPNOTSUPP);
+    }
+    let extra = rwf_status_flags(flags);
+    let ret = if offset == -1 {
+        do_writev(fd, iov, iovcnt, None, extra)
+    } else {
+        match check_offset(offset) {
+            Ok(off) => do_writev(fd, iov, iovcnt, Some(off), extra),
+            Err(e) => linux_err_to_isize(e),
+        }
+    };
+    
+    if ret > 0 && flags & (RWF_DSYNC | RWF_SYNC) != 0 {
+        if let Err(e) = sync_fd(fd, flags & RWF_SYNC == 0) {
+            return linux_err_to_isize(e);
+        }
+    }
+    ret
+}
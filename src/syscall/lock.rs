@@ -0,0 +1,464 @@
+/// Advisory file locks: flock and the fcntl F_GETLK/F_SETLK/F_SETLKW family
+///
+/// One table keyed by the (device, inode) of the locked node holds every lock.
+/// Byte-range locks are owned either by the process (classic POSIX locks) or
+/// by an open file description (F_OFD_*); the two kinds conflict with each
+/// other. flock locks are separate and always owned by an open file
+/// description. Blocking requests sleep until a lock is released and then
+/// check again; a process-owned fcntl wait that would close a cycle of
+/// waiters fails with EDEADLK instead.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use axerrno::LinuxError;
+use axio::SeekFrom;
+use axtask::WaitQueue;
+use linux_raw_sys::general::{
+    flock, F_RDLCK, F_UNLCK, F_WRLCK, LOCK_EX, LOCK_NB, LOCK_SH, LOCK_UN, O_PATH, SEEK_CUR,
+    SEEK_END, SEEK_SET,
+};
+
+use super::{
+    fd_table::{self, OpenFile, SpinLock},
+    linux_err_to_isize, thread,
+};
+
+/// Longest chain of waiters followed when looking for a deadlock, as on Linux
+const MAX_DEADLOCK_DEPTH: usize = 10;
+
+/// Holder of a byte-range lock
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Owner {
+    /// The process: F_SETLK and F_SETLKW
+    Process,
+    /// An open file description, by address: F_OFD_SETLK and F_OFD_SETLKW
+    File(usize),
+}
+
+/// Identity of an open file description for OFD and flock locks
+fn file_id(file: &OpenFile) -> usize {
+    file as *const OpenFile as usize
+}
+
+/// Byte-range lock on `start..end`; `end` is u64::MAX for a lock reaching past EOF
+#[derive(Clone, Copy)]
+struct RangeLock {
+    owner: Owner,
+    write: bool,
+    start: u64,
+    end: u64,
+}
+
+impl RangeLock {
+    fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.start < end && start < self.end
+    }
+}
+
+/// Whole-file flock lock held by the open file description `owner`
+struct FlockLock {
+    owner: usize,
+    exclusive: bool,
+}
+
+#[derive(Default)]
+struct NodeLocks {
+    ranges: Vec<RangeLock>,
+    flocks: Vec<FlockLock>,
+}
+
+impl NodeLocks {
+    /// First lock of another owner that keeps `owner` from locking `start..end`
+    fn conflict(&self, owner: Owner, write: bool, start: u64, end: u64) -> Option<&RangeLock> {
+        self.ranges
+            .iter()
+            .find(|l| l.owner != owner && l.overlaps(start, end) && (write || l.write))
+    }
+
+    /// Replace what `owner` holds in `start..end` with a lock of kind `write`,
+    /// or with nothing for None. Locks partly inside the range are split.
+    fn set_range(&mut self, owner: Owner, start: u64, end: u64, write: Option<bool>) {
+        let mut kept = Vec::with_capacity(self.ranges.len() + 2);
+        for l in self.ranges.drain(..) {
+            if l.owner != owner || !l.overlaps(start, end) {
+                kept.push(l);
+                continue;
+            }
+            if l.start < start {
+                kept.push(RangeLock { end: start, ..l });
+            }
+            if end < l.end {
+                kept.push(RangeLock { start: end, ..l });
+            }
+        }
+        if let Some(write) = write {
+            kept.push(RangeLock {
+                owner,
+                write,
+                start,
+                end,
+            });
+        }
+        self.ranges = kept;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ranges.is_empty() && self.flocks.is_empty()
+    }
+}
+
+/// A blocked process-owned fcntl request: its owner and the owner it waits for
+#[derive(Clone, Copy)]
+struct Waiter {
+    owner: Owner,
+    blocker: Owner,
+}
+
+struct LockTable {
+    nodes: BTreeMap<(u64, u64), NodeLocks>,
+    /// Blocked process-owned fcntl requests, by the id of the waiting task
+    waiting: BTreeMap<u64, Waiter>,
+}
+
+impl LockTable {
+    /// Whether `owner` waiting for `blocker` would close a cycle of waiters.
+    /// Every waiter of an owner is followed, since several tasks can block at once.
+    fn would_deadlock(&self, owner: Owner, blocker: Owner, depth: usize) -> bool {
+        if blocker == owner {
+            return true;
+        }
+        if depth >= MAX_DEADLOCK_DEPTH {
+            return false;
+        }
+        self.waiting
+            .values()
+            .filter(|w| w.owner == blocker)
+            .any(|w| self.would_deadlock(owner, w.blocker, depth + 1))
+    }
+
+    /// Forget the locks of a node once none are left
+    fn prune(&mut self, key: (u64, u64)) {
+        if self.nodes.get(&key).is_some_and(NodeLocks::is_empty) {
+            self.nodes.remove(&key);
+        }
+    }
+}
+
+static LOCKS: SpinLock<LockTable> = SpinLock::new(LockTable {
+    nodes: BTreeMap::new(),
+    waiting: BTreeMap::new(),
+});
+
+/// Blocked lock requests sleep here until some lock is released
+static RELEASED: WaitQueue = WaitQueue::new();
+
+/// Bumped on every release, so a waiter can tell it missed none
+static RELEASES: AtomicU64 = AtomicU64::new(0);
+
+/// Wake blocked lock requests after locks were dropped or narrowed
+fn wake_waiters() {
+    RELEASES.fetch_add(1, Ordering::Release);
+    RELEASED.notify_all(false);
+}
+
+/// Sleep until a release happens after the one numbered `seen`
+fn wait_release(seen: u64) {
+    RELEASED.wait_until(|| RELEASES.load(Ordering::Acquire) != seen);
+}
+
+/// Id of the running task, which keys its entry in the waiting list
+fn current_task() -> u64 {
+    axtask::current().id().as_u64()
+}
+
+/// (device, inode) of the node behind `file`
+fn node_key(file: &OpenFile) -> Result<(u64, u64), LinuxError> {
+    let meta = file.metadata()?;
+    Ok((meta.device, meta.inode))
+}
+
+/// Take a byte-range lock, waiting for conflicting locks to go away if `wait` is set
+fn lock_range(
+    key: (u64, u64),
+    owner: Owner,
+    write: bool,
+    start: u64,
+    end: u64,
+    wait: bool,
+) -> Result<(), LinuxError> {
+    let task = current_task();
+    loop {
+        let seen = RELEASES.load(Ordering::Acquire);
+        {
+            let mut table = LOCKS.lock();
+            let blocker = table
+                .nodes
+                .get(&key)
+                .and_then(|node| node.conflict(owner, write, start, end))
+                .map(|l| l.owner);
+            match blocker {
+                None => {
+                    table.waiting.remove(&task);
+                    table
+                        .nodes
+                        .entry(key)
+                        .or_default()
+                        .set_range(owner, start, end, Some(write));
+                    drop(table);
+                    // Converting a write lock to a read lock may unblock others
+                    wake_waiters();
+                    return Ok(());
+                }
+                Some(_) if !wait => return Err(LinuxError::EAGAIN),
+                // OFD locks have no owning process, so Linux never reports EDEADLK for them
+                Some(_) if matches!(owner, Owner::File(_)) => {}
+                Some(blocker) => {
+                    if table.would_deadlock(owner, blocker, 0) {
+                        table.waiting.remove(&task);
+                        return Err(LinuxError::EDEADLK);
+                    }
+                    table.waiting.insert(task, Waiter { owner, blocker });
+                }
+            }
+        }
+        wait_release(seen);
+    }
+}
+
+/// Load the `struct flock` at `arg`
+fn load_flock(arg: usize) -> Result<flock, LinuxError> {
+    if arg == 0 {
+        return Err(LinuxError::EFAULT);
+    }
+    Ok(unsafe { (arg as *const flock).read_unaligned() })
+}
+
+/// Byte range a `struct flock` describes, as `start..end`
+fn flock_range(file: &OpenFile, fl: &flock) -> Result<(u64, u64), LinuxError> {
+    let base = match fl.l_whence as u32 {
+        SEEK_SET => 0,
+        SEEK_CUR => file.object.seek(SeekFrom::Current(0))? as i64,
+        SEEK_END => file.metadata()?.size as i64,
+        _ => return Err(LinuxError::EINVAL),
+    };
+    let start = base.checked_add(fl.l_start).ok_or(LinuxError::EOVERFLOW)?;
+    // A negative length locks the bytes before `start`; zero means up to EOF and beyond
+    let (start, end) = match fl.l_len {
+        0 => (start, None),
+        len if len > 0 => (start, Some(start.checked_add(len).ok_or(LinuxError::EOVERFLOW)?)),
+        len => (start.checked_add(len).ok_or(LinuxError::EINVAL)?, Some(start)),
+    };
+    if start < 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok((start as u64, end.map_or(u64::MAX, |end| end as u64)))
+}
+
+/// fcntl F_GETLK and F_OFD_GETLK: report the first lock that would block the request at `arg`
+pub fn get_lock(file: &OpenFile, ofd: bool, arg: usize) -> Result<isize, LinuxError> {
+    let mut fl = load_flock(arg)?;
+    if ofd && fl.l_pid != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let write = match fl.l_type as u32 {
+        F_RDLCK => false,
+        F_WRLCK => true,
+        _ => return Err(LinuxError::EINVAL),
+    };
+    let (start, end) = flock_range(file, &fl)?;
+    let owner = if ofd {
+        Owner::File(file_id(file))
+    } else {
+        Owner::Process
+    };
+    let key = node_key(file)?;
+
+    let blocker = LOCKS
+        .lock()
+        .nodes
+        .get(&key)
+        .and_then(|node| node.conflict(owner, write, start, end).copied());
+    match blocker {
+        Some(l) => {
+            fl.l_type = (if l.write { F_WRLCK } else { F_RDLCK }) as _;
+            fl.l_whence = SEEK_SET as _;
+            fl.l_start = l.start as _;
+            fl.l_len = if l.end == u64::MAX { 0 } else { (l.end - l.start) as _ };
+            // OFD locks belong to no process
+            fl.l_pid = match l.owner {
+                Owner::Process => thread::get_pid() as _,
+                Owner::File(_) => -1,
+            };
+        }
+        None => fl.l_type = F_UNLCK as _,
+    }
+    unsafe { (arg as *mut flock).write_unaligned(fl) };
+    Ok(0)
+}
+
+/// fcntl F_SETLK/F_SETLKW and F_OFD_SETLK/F_OFD_SETLKW: lock or unlock the range at `arg`.
+/// Without `wait` a conflict is EAGAIN.
+pub fn set_lock(file: &OpenFile, ofd: bool, wait: bool, arg: usize) -> Result<isize, LinuxError> {
+    if file.status_flags() & O_PATH != 0 {
+        return Err(LinuxError::EBADF);
+    }
+    let fl = load_flock(arg)?;
+    if ofd && fl.l_pid != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let (start, end) = flock_range(file, &fl)?;
+    let owner = if ofd {
+        Owner::File(file_id(file))
+    } else {
+        Owner::Process
+    };
+    let key = node_key(file)?;
+
+    match fl.l_type as u32 {
+        F_RDLCK => {
+            file.check_readable()?;
+            lock_range(key, owner, false, start, end, wait)?;
+        }
+        F_WRLCK => {
+            file.check_writable()?;
+            lock_range(key, owner, true, start, end, wait)?;
+        }
+        F_UNLCK => {
+            let mut table = LOCKS.lock();
+            if let Some(node) = table.nodes.get_mut(&key) {
+                node.set_range(owner, start, end, None);
+            }
+            table.prune(key);
+            drop(table);
+            wake_waiters();
+        }
+        _ => return Err(LinuxError::EINVAL),
+    }
+    Ok(0)
+}
+
+/// Drop the process's byte-range locks on the node behind `file`.
+/// Closing any fd of a node does this, whichever fd took the locks, as POSIX requires.
+pub fn release_posix(file: &OpenFile) {
+    if LOCKS.lock().nodes.is_empty() {
+        return;
+    }
+    let Ok(key) = node_key(file) else {
+        return;
+    };
+    let mut table = LOCKS.lock();
+    if let Some(node) = table.nodes.get_mut(&key) {
+        node.ranges.retain(|l| l.owner != Owner::Process);
+    }
+    table.prune(key);
+    drop(table);
+    wake_waiters();
+}
+
+/// Drop the OFD and flock locks of an open file description that is going away
+pub fn release_file(file: &OpenFile) {
+    let id = file_id(file);
+    let mut table = LOCKS.lock();
+    table.nodes.retain(|_, node| {
+        node.ranges.retain(|l| l.owner != Owner::File(id));
+        node.flocks.retain(|l| l.owner != id);
+        !node.is_empty()
+    });
+    drop(table);
+    wake_waiters();
+}
+
+/// Drop every lock; the process is the only lock owner, so this is its exit
+pub fn release_all() {
+    let mut table = LOCKS.lock();
+    table.nodes.clear();
+    table.waiting.clear();
+    drop(table);
+    wake_waiters();
+}
+
+/// Syscall: flock(2) - Apply or remove a whole-file advisory lock
+/// `op` is LOCK_SH, LOCK_EX or LOCK_UN, optionally with LOCK_NB.
+/// Converting a held lock first releases it, as on Linux.
+/// Returns 0 on success, negative error on failure (EAGAIN, i.e. EWOULDBLOCK, with LOCK_NB)
+pub fn sys_flock(fd: i32, op: u32) -> isize {
+    let res = (|| -> Result<(), LinuxError> {
+        let file = fd_table::get_file(fd).ok_or(LinuxError::EBADF)?;
+        if file.status_flags() & O_PATH != 0 {
+            return Err(LinuxError::EBADF);
+        }
+        let exclusive = match op & !LOCK_NB {
+            LOCK_SH => false,
+            LOCK_EX => true,
+            LOCK_UN => {
+                release_flock(&file)?;
+                return Ok(());
+            }
+            _ => return Err(LinuxError::EINVAL),
+        };
+        let key = node_key(&file)?;
+        let id = file_id(&file);
+
+        // Already holding the lock in the requested mode is a no-op
+        let mut table = LOCKS.lock();
+        if let Some(node) = table.nodes.get_mut(&key) {
+            if node
+                .flocks
+                .iter()
+                .any(|l| l.owner == id && l.exclusive == exclusive)
+            {
+                return Ok(());
+            }
+            let held = node.flocks.len();
+            node.flocks.retain(|l| l.owner != id);
+            if node.flocks.len() != held {
+                table.prune(key);
+                drop(table);
+                wake_waiters();
+            }
+        }
+
+        loop {
+            let seen = RELEASES.load(Ordering::Acquire);
+            {
+                let mut table = LOCKS.lock();
+                let blocked = table.nodes.get(&key).is_some_and(|node| {
+                    node.flocks
+                        .iter()
+                        .any(|l| l.owner != id && (exclusive || l.exclusive))
+                });
+                if !blocked {
+                    table.nodes.entry(key).or_default().flocks.push(FlockLock {
+                        owner: id,
+                        exclusive,
+                    });
+                    return Ok(());
+                }
+                if op & LOCK_NB != 0 {
+                    return Err(LinuxError::EAGAIN);
+                }
+            }
+            wait_release(seen);
+        }
+    })();
+
+    match res {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Drop the flock lock `file` holds, if any
+fn release_flock(file: &OpenFile) -> Result<(), LinuxError> {
+    let key = node_key(file)?;
+    let id = file_id(file);
+    let mut table = LOCKS.lock();
+    if let Some(node) = table.nodes.get_mut(&key) {
+        node.flocks.retain(|l| l.owner != id);
+    }
+    table.prune(key);
+    drop(table);
+    wake_waiters();
+    Ok(())
+}
@@ -0,0 +1,343 @@
+/// Anonymous memory files (memfd_create)
+///
+/// A memfd is a regular file that lives only in kernel memory and disappears
+/// with its last fd. Its size can be frozen with file seals (F_ADD_SEALS);
+/// readlink of /proc/self/fd shows it as `/memfd:<name> (deleted)`, like Linux.
+/// MAP_SHARED mappings point straight at the memfd's pages, so stores through
+/// a mapping and writes through an fd see each other.
+
+use alloc::{
+    alloc::{alloc_zeroed, dealloc},
+    format,
+    string::String,
+};
+use core::{alloc::Layout, any::Any, ptr::NonNull};
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::{Metadata, NodePermission, NodeType};
+use axio::SeekFrom;
+use axpoll::IoEvents;
+use linux_raw_sys::general::{
+    F_SEAL_EXEC, F_SEAL_FUTURE_WRITE, F_SEAL_GROW, F_SEAL_SEAL, F_SEAL_SHRINK, F_SEAL_WRITE,
+    MFD_ALLOW_SEALING, MFD_CLOEXEC, MFD_EXEC, MFD_NOEXEC_SEAL, O_APPEND, O_LARGEFILE, O_RDWR,
+};
+
+use super::{
+    ax_err_to_linux,
+    fd_table::{self, SpinLock, FD_CLOEXEC},
+    file::{anon_inode_metadata, FileLike},
+    fs::load_user_cstring,
+    linux_err_to_isize,
+};
+
+/// Longest name memfd_create accepts, excluding the "memfd:" prefix
+const MAX_NAME_LEN: usize = 249;
+
+/// Seals F_ADD_SEALS knows about
+const ALL_SEALS: u32 =
+    F_SEAL_SEAL | F_SEAL_SHRINK | F_SEAL_GROW | F_SEAL_WRITE | F_SEAL_FUTURE_WRITE | F_SEAL_EXEC;
+
+const PAGE_SIZE: usize = 4096;
+
+/// Page-aligned contents of a memfd
+///
+/// Shared mappings hand out addresses inside these pages, and there is no
+/// munmap, so the first shared mapping pins them for good: they never move
+/// again and are never freed. A pinned memfd can still shrink and grow back
+/// within its capacity; growing past it fails with ENOMEM.
+struct Pages {
+    ptr: NonNull<u8>,
+    len: usize,
+    /// Allocated bytes, a multiple of PAGE_SIZE
+    capacity: usize,
+    pinned: bool,
+}
+
+// The pages are only reached through the memfd's lock, or through mappings
+unsafe impl Send for Pages {}
+
+impl Pages {
+    const fn new() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            len: 0,
+            capacity: 0,
+            pinned: false,
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    fn layout(capacity: usize) -> Result<Layout, LinuxError> {
+        Layout::from_size_align(capacity, PAGE_SIZE).map_err(|_| LinuxError::EFBIG)
+    }
+
+    /// Make sure at least `size` bytes are allocated
+    fn reserve(&mut self, size: usize) -> Result<(), LinuxError> {
+        if size <= self.capacity {
+            return Ok(());
+        }
+        if self.pinned {
+            return Err(LinuxError::ENOMEM);
+        }
+        let capacity = size
+            .max(self.capacity * 2)
+            .checked_next_multiple_of(PAGE_SIZE)
+            .ok_or(LinuxError::EFBIG)?;
+        let ptr = NonNull::new(unsafe { alloc_zeroed(Self::layout(capacity)?) })
+            .ok_or(LinuxError::ENOMEM)?;
+        unsafe { core::ptr::copy_nonoverlapping(self.ptr.as_ptr(), ptr.as_ptr(), self.len) };
+        self.free();
+        self.ptr = ptr;
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    /// Set the size; bytes that come into range read as zeros
+    fn resize(&mut self, len: usize) -> Result<(), LinuxError> {
+        self.reserve(len)?;
+        if len > self.len {
+            // A shared mapping may have dirtied the bytes past the old end
+            unsafe { core::ptr::write_bytes(self.ptr.as_ptr().add(self.len), 0, len - self.len) };
+        }
+        self.len = len;
+        Ok(())
+    }
+
+    fn free(&mut self) {
+        if self.capacity != 0 {
+            // `reserve` allocated exactly this layout
+            let layout = unsafe { Layout::from_size_align_unchecked(self.capacity, PAGE_SIZE) };
+            unsafe { dealloc(self.ptr.as_ptr(), layout) };
+        }
+    }
+}
+
+impl Drop for Pages {
+    /// Pinned pages outlive the memfd: its mappings still point at them
+    fn drop(&mut self) {
+        if !self.pinned {
+            self.free();
+        }
+    }
+}
+
+struct MemState {
+    pages: Pages,
+    pos: u64,
+    seals: u32,
+    /// Shared writable mappings ever made; they are never unmapped
+    writable_maps: usize,
+}
+
+/// A memfd object
+pub struct MemFd {
+    name: String,
+    /// Permission bits; MFD_NOEXEC_SEAL files start without execute permission
+    mode: u32,
+    state: SpinLock<MemState>,
+}
+
+impl MemFd {
+    /// Current seals (F_GET_SEALS)
+    pub fn seals(&self) -> u32 {
+        self.state.lock().seals
+    }
+
+    /// Add seals (F_ADD_SEALS); EPERM once F_SEAL_SEAL is set.
+    /// F_SEAL_WRITE fails with EBUSY while shared writable mappings exist.
+    pub fn add_seals(&self, seals: u32) -> Result<(), LinuxError> {
+        if seals & !ALL_SEALS != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let mut state = self.state.lock();
+        if state.seals & F_SEAL_SEAL != 0 {
+            return Err(LinuxError::EPERM);
+        }
+        if seals & F_SEAL_WRITE != 0 && state.writable_maps > 0 {
+            return Err(LinuxError::EBUSY);
+        }
+        state.seals |= seals;
+        Ok(())
+    }
+
+    /// Write `buf` at `offset`, honoring the write and grow seals
+    fn write_at_locked(state: &mut MemState, buf: &[u8], offset: u64) -> Result<usize, LinuxError> {
+        if state.seals & (F_SEAL_WRITE | F_SEAL_FUTURE_WRITE) != 0 {
+            return Err(LinuxError::EPERM);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let offset = usize::try_from(offset).map_err(|_| LinuxError::EFBIG)?;
+        let end = offset.checked_add(buf.len()).ok_or(LinuxError::EFBIG)?;
+        if end > state.pages.len {
+            if state.seals & F_SEAL_GROW != 0 {
+                return Err(LinuxError::EPERM);
+            }
+            state.pages.resize(end)?;
+        }
+        state.pages.as_mut_slice()[offset..end].copy_from_slice(buf);
+        Ok(buf.len())
+    }
+}
+
+/// Copy from `data` at `offset` into `buf`; nothing past the end
+fn read_from(data: &[u8], buf: &mut [u8], offset: u64) -> usize {
+    let start = (offset.min(data.len() as u64)) as usize;
+    let n = buf.len().min(data.len() - start);
+    buf[..n].copy_from_slice(&data[start..start + n]);
+    n
+}
+
+impl FileLike for MemFd {
+    fn read(&self, buf: &mut [u8], _flags: u32) -> Result<usize, LinuxError> {
+        let mut state = self.state.lock();
+        let n = read_from(state.pages.as_slice(), buf, state.pos);
+        state.pos += n as u64;
+        Ok(n)
+    }
+
+    fn write(&self, buf: &[u8], flags: u32) -> Result<usize, LinuxError> {
+        let mut state = self.state.lock();
+        if flags & O_APPEND != 0 {
+            state.pos = state.pages.len as u64;
+        }
+        let pos = state.pos;
+        let n = Self::write_at_locked(&mut state, buf, pos)?;
+        state.pos += n as u64;
+        Ok(n)
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize, LinuxError> {
+        Ok(read_from(self.state.lock().pages.as_slice(), buf, offset))
+    }
+
+    fn write_at(&self, buf: &[u8], offset: u64, _flags: u32) -> Result<usize, LinuxError> {
+        Self::write_at_locked(&mut self.state.lock(), buf, offset)
+    }
+
+    fn seek(&self, pos: SeekFrom) -> Result<u64, LinuxError> {
+        let mut state = self.state.lock();
+        let new = match pos {
+            SeekFrom::Start(off) => Some(off),
+            SeekFrom::Current(off) => state.pos.checked_add_signed(off),
+            SeekFrom::End(off) => (state.pages.len as u64).checked_add_signed(off),
+        };
+        state.pos = new.filter(|&p| p <= i64::MAX as u64).ok_or(LinuxError::EINVAL)?;
+        Ok(state.pos)
+    }
+
+    fn truncate(&self, len: u64) -> Result<(), LinuxError> {
+        let mut state = self.state.lock();
+        let len = usize::try_from(len).map_err(|_| LinuxError::EFBIG)?;
+        let size = state.pages.len;
+        if (len < size && state.seals & F_SEAL_SHRINK != 0)
+            || (len > size && state.seals & F_SEAL_GROW != 0)
+        {
+            return Err(LinuxError::EPERM);
+        }
+        state.pages.resize(len)
+    }
+
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        let size = self.state.lock().pages.len as u64;
+        let mut meta = anon_inode_metadata(self as *const Self as u64);
+        meta.node_type = NodeType::RegularFile;
+        meta.mode = NodePermission::from_bits_truncate(self.mode);
+        meta.size = size;
+        meta.blocks = size.div_ceil(512);
+        Ok(meta)
+    }
+
+    fn poll(&self) -> IoEvents {
+        IoEvents::IN | IoEvents::OUT
+    }
+
+    fn mmap(&self, buf: &mut [u8], offset: u64) -> Result<(), LinuxError> {
+        // Bytes past the end stay zero
+        read_from(self.state.lock().pages.as_slice(), buf, offset);
+        Ok(())
+    }
+
+    /// Map the memfd's own pages; the part past the end reads as zeros.
+    /// Writable mappings are refused once F_SEAL_WRITE or F_SEAL_FUTURE_WRITE is set.
+    fn mmap_shared(&self, offset: u64, len: usize, writable: bool) -> Result<*mut u8, LinuxError> {
+        let mut state = self.state.lock();
+        if writable && state.seals & (F_SEAL_WRITE | F_SEAL_FUTURE_WRITE) != 0 {
+            return Err(LinuxError::EPERM);
+        }
+        let offset = usize::try_from(offset).map_err(|_| LinuxError::ENOMEM)?;
+        let end = offset.checked_add(len).ok_or(LinuxError::ENOMEM)?;
+        state.pages.reserve(end)?;
+        state.pages.pinned = true;
+        if writable {
+            state.writable_maps += 1;
+        }
+        Ok(unsafe { state.pages.ptr.as_ptr().add(offset) })
+    }
+
+    fn sync(&self, _data_only: bool) -> Result<(), LinuxError> {
+        // Nothing to flush: memory is the backing store
+        Ok(())
+    }
+
+    fn display_name(&self) -> Option<String> {
+        Some(format!("/{} (deleted)", self.name))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Syscall: memfd_create(2) - Create an anonymous file in memory
+/// Supported flags: MFD_CLOEXEC, MFD_ALLOW_SEALING, MFD_EXEC, MFD_NOEXEC_SEAL.
+/// Without MFD_ALLOW_SEALING the file starts with F_SEAL_SEAL, so no seals can be added.
+/// Returns the new fd on success, negative error on failure
+pub fn sys_memfd_create(name: usize, flags: u32) -> isize {
+    let res = (|| -> Result<i32, LinuxError> {
+        if flags & !(MFD_CLOEXEC | MFD_ALLOW_SEALING | MFD_EXEC | MFD_NOEXEC_SEAL) != 0 {
+            // MFD_HUGETLB and its page-size bits need hugetlbfs, which does not exist here
+            return Err(LinuxError::EINVAL);
+        }
+        if flags & MFD_EXEC != 0 && flags & MFD_NOEXEC_SEAL != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let name = load_user_cstring(name).map_err(ax_err_to_linux)?;
+        if name.len() > MAX_NAME_LEN {
+            return Err(LinuxError::EINVAL);
+        }
+
+        let (mode, seals) = if flags & MFD_NOEXEC_SEAL != 0 {
+            (0o666, F_SEAL_EXEC)
+        } else if flags & MFD_ALLOW_SEALING != 0 {
+            (0o777, 0)
+        } else {
+            (0o777, F_SEAL_SEAL)
+        };
+        let memfd = MemFd {
+            name: format!("memfd:{}", name),
+            mode,
+            state: SpinLock::new(MemState {
+                pages: Pages::new(),
+                pos: 0,
+                seals,
+                writable_maps: 0,
+            }),
+        };
+        let fd_flags = if flags & MFD_CLOEXEC != 0 { FD_CLOEXEC } else { 0 };
+        fd_table::add_file(memfd, O_RDWR | O_LARGEFILE, fd_flags)
+    })();
+
+    match res {
+        Ok(fd) => fd as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
@@ -1,7 +1,10 @@
-use alloc::alloc::alloc;
+use alloc::alloc::{alloc, dealloc};
 use alloc::vec::Vec;
 use core::alloc::Layout;
-use super::ENOSYS;
+
+use axerrno::LinuxError;
+
+use super::{fd_table, linux_err_to_isize};
 
 static mut BRK_END: usize = 0;
 
@@ -9,14 +12,12 @@ static mut BRK_END: usize = 0;
 #[allow(dead_code)]
 static mut MMAP_REGIONS: Option<Vec<(usize, usize)>> = None;
 
-#[allow(dead_code)]
 const MAP_SHARED: usize = 0x01;
 #[allow(dead_code)]
 const MAP_PRIVATE: usize = 0x02;
 const MAP_ANONYMOUS: usize = 0x20;
 #[allow(dead_code)]
 const PROT_READ: usize = 0x1;
-#[allow(dead_code)]
 const PROT_WRITE: usize = 0x2;
 
 pub fn sys_brk(addr: usize) -> isize {
@@ -35,19 +36,54 @@ pub fn sys_brk(addr: usize) -> isize {
     }
 }
 
-pub fn sys_mmap(_addr: usize, len: usize, _prot: usize, flags: usize, _fd: usize, _offset: usize) -> isize {
-    // Only support anonymous mappings for now
-    if (flags & MAP_ANONYMOUS) == 0 {
-        return ENOSYS;
-    }
-    
+/// Syscall: mmap(2) - Map memory
+/// MAP_SHARED file mappings use the object's own memory (`FileLike::mmap_shared`),
+/// which only memfds have. Other file mappings are filled from the file through
+/// `FileLike::mmap`; that copy is private, so shared writable mappings of such
+/// files are refused with ENODEV.
+pub fn sys_mmap(_addr: usize, len: usize, prot: usize, flags: usize, fd: usize, offset: usize) -> isize {
     if len == 0 {
         return -22; // EINVAL
     }
     
+    let file = if (flags & MAP_ANONYMOUS) == 0 {
+        let file = match fd_table::get_file(fd as i32) {
+            Some(f) => f,
+            None => return linux_err_to_isize(LinuxError::EBADF),
+        };
+        if offset % 4096 != 0 {
+            return linux_err_to_isize(LinuxError::EINVAL);
+        }
+        if file.check_readable().is_err() {
+            return linux_err_to_isize(LinuxError::EACCES);
+        }
+        // Stores through a shared writable mapping reach the file
+        if (flags & MAP_SHARED) != 0 && (prot & PROT_WRITE) != 0 && file.check_writable().is_err() {
+            return linux_err_to_isize(LinuxError::EACCES);
+        }
+        Some(file)
+    } else {
+        None
+    };
+    
     // Align length to page size (4096)
     let aligned_len = (len + 4095) & !4095;
     
+    if let Some(file) = &file {
+        if (flags & MAP_SHARED) != 0 {
+            let writable = (prot & PROT_WRITE) != 0;
+            match file
+                .object
+                .mmap_shared(offset as u64, aligned_len, writable)
+            {
+                Ok(ptr) => return ptr as isize,
+                // Read-only mappings of other files get a snapshot instead
+                Err(LinuxError::ENODEV) if !writable => {}
+                Err(e) => return linux_err_to_isize(e),
+            }
+        }
+    }
+    
     // Try to allocate memory
     let layout = match Layout::from_size_align(aligned_len, 4096) {
         Ok(l) => l,
@@ -63,6 +99,14 @@ pub fn sys_mmap(_addr: usize, len: usize, _prot: usize, flags: usize, _fd: usize
         // Initialize memory to zero
         core::ptr::write_bytes(ptr, 0, aligned_len);
         
+        if let Some(file) = file {
+            let buf = core::slice::from_raw_parts_mut(ptr, aligned_len);
+            if let Err(e) = file.object.mmap(buf, offset as u64) {
+                dealloc(ptr, layout);
+                return linux_err_to_isize(e);
+            }
+        }
+        
         // Note: We skip allocation tracking due to mutable static restrictions
         // In production, use a SpinLock<Vec<>> or similar pattern
         
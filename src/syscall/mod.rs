@@ -1,19 +1,94 @@
+pub mod console;
+pub mod dir;
 pub mod dispatch;
+pub mod epoll;
+pub mod eventfd;
 pub mod fd_table;
+pub mod file;
 pub mod fs;
+pub mod inotify;
+pub mod io;
+pub mod lock;
+pub mod memfd;
 pub mod mm;
+pub mod namespace;
+pub mod path;
+pub mod pipe;
+pub mod poll;
+pub mod signal;
+pub mod signalfd;
+pub mod stat;
 pub mod sync;
 pub mod sys;
 pub mod table;
 pub mod task;
 pub mod thread;
+pub mod timerfd;
 
 pub use dispatch::handle_syscall;
 
 pub const ENOSYS: isize = -38;
 
-use axerrno::LinuxError;
+use axerrno::{AxError, LinuxError};
 
 pub fn linux_err_to_isize(err: LinuxError) -> isize {
 	-(err as i32 as isize)
 }
+
+/// Map an arceos error (axerrno, axio and axfs-ng-vfs all use `AxError`)
+/// to the errno Linux reports for the same condition.
+/// Every handler goes through this one mapping.
+pub fn ax_err_to_linux(err: AxError) -> LinuxError {
+	match err {
+		AxError::AddrInUse => LinuxError::EADDRINUSE,
+		AxError::AlreadyConnected => LinuxError::EISCONN,
+		AxError::AlreadyExists => LinuxError::EEXIST,
+		AxError::ArgumentListTooLong => LinuxError::E2BIG,
+		AxError::BadAddress => LinuxError::EFAULT,
+		AxError::BadFileDescriptor => LinuxError::EBADF,
+		AxError::BadState => LinuxError::EINVAL,
+		AxError::BrokenPipe => LinuxError::EPIPE,
+		AxError::ConnectionRefused => LinuxError::ECONNREFUSED,
+		AxError::ConnectionReset => LinuxError::ECONNRESET,
+		AxError::CrossesDevices => LinuxError::EXDEV,
+		AxError::DirectoryNotEmpty => LinuxError::ENOTEMPTY,
+		AxError::FilesystemLoop => LinuxError::ELOOP,
+		AxError::IllegalBytes => LinuxError::EILSEQ,
+		AxError::InProgress => LinuxError::EINPROGRESS,
+		AxError::Interrupted => LinuxError::EINTR,
+		AxError::InvalidData => LinuxError::EINVAL,
+		AxError::InvalidExecutable => LinuxError::ENOEXEC,
+		AxError::InvalidInput => LinuxError::EINVAL,
+		AxError::Io => LinuxError::EIO,
+		AxError::IsADirectory => LinuxError::EISDIR,
+		AxError::NameTooLong => LinuxError::ENAMETOOLONG,
+		AxError::NoMemory => LinuxError::ENOMEM,
+		AxError::NoSuchDevice => LinuxError::ENODEV,
+		AxError::NoSuchProcess => LinuxError::ESRCH,
+		AxError::NotADirectory => LinuxError::ENOTDIR,
+		AxError::NotASocket => LinuxError::ENOTSOCK,
+		AxError::NotATty => LinuxError::ENOTTY,
+		AxError::NotConnected => LinuxError::ENOTCONN,
+		AxError::NotFound => LinuxError::ENOENT,
+		AxError::OperationNotPermitted => LinuxError::EPERM,
+		AxError::OperationNotSupported => LinuxError::EOPNOTSUPP,
+		AxError::OutOfRange => LinuxError::ERANGE,
+		AxError::PermissionDenied => LinuxError::EACCES,
+		AxError::ReadOnlyFilesystem => LinuxError::EROFS,
+		AxError::ResourceBusy => LinuxError::EBUSY,
+		AxError::StorageFull => LinuxError::ENOSPC,
+		AxError::TimedOut => LinuxError::ETIMEDOUT,
+		AxError::TooManyOpenFiles => LinuxError::EMFILE,
+		AxError::UnexpectedEof => LinuxError::EIO,
+		AxError::Unsupported => LinuxError::ENOSYS,
+		AxError::WouldBlock => LinuxError::EAGAIN,
+		AxError::WriteZero => LinuxError::EIO,
+		// Errors that already carry a Linux errno (EFBIG, ESPIPE, ...) or kinds added later
+		_ => LinuxError::from(err),
+	}
+}
+
+/// Map an arceos error to a negative errno syscall return value
+pub fn ax_err_to_isize(err: AxError) -> isize {
+	linux_err_to_isize(ax_err_to_linux(err))
+}
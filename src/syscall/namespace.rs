@@ -0,0 +1,315 @@
+/// Namespace-modifying syscalls: mkdirat, unlinkat, renameat2, linkat, symlinkat
+///
+/// Each operation resolves the parent directory of the affected name(s)
+/// through `path::parent_at` and then acts on the VFS directory node.
+/// The syscall-level checks that the VFS cannot know about (wrong node type
+/// for the call, "." and "..", cross-mount links) are done here, and
+/// successful changes are reported to inotify watchers.
+
+use alloc::string::String;
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::{Location, NodePermission, NodeType};
+use linux_raw_sys::general::{
+    IN_ATTRIB, IN_CREATE, IN_DELETE, IN_DELETE_SELF, IN_MOVED_FROM, IN_MOVED_TO, IN_MOVE_SELF,
+};
+
+use super::{
+    ax_err_to_linux, fd_table,
+    fs::load_user_cstring,
+    inotify, linux_err_to_isize,
+    path::{fs_context_at, parent_at, AT_FDCWD},
+};
+
+const AT_REMOVEDIR: u32 = 0x200;
+const AT_SYMLINK_FOLLOW: u32 = 0x400;
+const AT_EMPTY_PATH: u32 = 0x1000;
+
+const RENAME_NOREPLACE: u32 = 1 << 0;
+const RENAME_EXCHANGE: u32 = 1 << 1;
+const RENAME_WHITEOUT: u32 = 1 << 2;
+
+/// Load a user path, mapping failures to the errno the caller should see
+fn load_path(ptr: usize) -> Result<String, LinuxError> {
+    let path = load_user_cstring(ptr).map_err(ax_err_to_linux)?;
+    if path.is_empty() {
+        return Err(LinuxError::ENOENT);
+    }
+    Ok(path)
+}
+
+/// Look up `name` in `dir` without following a final symlink
+fn lookup(dir: &Location, name: &str) -> Result<Location, LinuxError> {
+    dir.lookup_no_follow(name).map_err(ax_err_to_linux)
+}
+
+/// Fail with EXDEV unless both directories live on the same filesystem
+fn check_same_fs(a: &Location, b: &Location) -> Result<(), LinuxError> {
+    let dev_a = a.metadata().map_err(ax_err_to_linux)?.device;
+    let dev_b = b.metadata().map_err(ax_err_to_linux)?.device;
+    if dev_a == dev_b {
+        Ok(())
+    } else {
+        Err(LinuxError::EXDEV)
+    }
+}
+
+fn to_isize(res: Result<(), LinuxError>) -> isize {
+    match res {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: mkdirat(2) - Create a directory
+/// Returns 0 on success, negative error on failure (EEXIST if the name is taken)
+pub fn sys_mkdirat(dirfd: isize, path: usize, mode: u32) -> isize {
+    to_isize((|| -> Result<(), LinuxError> {
+        let path = load_path(path)?;
+        let (dir, name) = parent_at(dirfd, &path)?;
+        if name == "." || name == ".." || lookup(&dir, &name).is_ok() {
+            return Err(LinuxError::EEXIST);
+        }
+        let perm = NodePermission::from_bits_truncate((mode & 0o7777) as u16);
+        dir.create(&name, NodeType::Directory, perm)
+            .map_err(ax_err_to_linux)?;
+        inotify::notify_entry(&dir, &name, IN_CREATE, 0, true);
+        Ok(())
+    })())
+}
+
+/// Syscall: unlinkat(2) - Remove a directory entry
+/// Without AT_REMOVEDIR this is unlink(2) and directories give EISDIR;
+/// with it this is rmdir(2) and non-directories give ENOTDIR
+/// Returns 0 on success, negative error on failure
+pub fn sys_unlinkat(dirfd: isize, path: usize, flags: u32) -> isize {
+    if flags & !AT_REMOVEDIR != 0 {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    let remove_dir = flags & AT_REMOVEDIR != 0;
+    
+    to_isize((|| -> Result<(), LinuxError> {
+        let path = load_path(path)?;
+        let (dir, name) = parent_at(dirfd, &path)?;
+        match name.as_str() {
+            "." if remove_dir => return Err(LinuxError::EINVAL),
+            ".." if remove_dir => return Err(LinuxError::ENOTEMPTY),
+            "." | ".." => return Err(LinuxError::EISDIR),
+            _ => {}
+        }
+        
+        let target = lookup(&dir, &name)?;
+        match (remove_dir, target.is_dir()) {
+            (false, true) => return Err(LinuxError::EISDIR),
+            (true, false) => return Err(LinuxError::ENOTDIR),
+            _ => {}
+        }
+        dir.unlink(&name, remove_dir).map_err(ax_err_to_linux)?;
+        
+        inotify::notify_entry(&dir, &name, IN_DELETE, 0, remove_dir);
+        // The node itself is gone once its last link is
+        let gone = remove_dir || target.metadata().map_or(true, |m| m.nlink == 0);
+        inotify::notify_self(&target, if gone { IN_DELETE_SELF } else { IN_ATTRIB });
+        Ok(())
+    })())
+}
+
+/// Report a completed rename of `node` from `src_name` in `src_dir` to `dst_name` in `dst_dir`
+fn notify_moved(
+    src_dir: &Location,
+    src_name: &str,
+    dst_dir: &Location,
+    dst_name: &str,
+    node: &Location,
+) {
+    let cookie = inotify::next_cookie();
+    let is_dir = node.is_dir();
+    inotify::notify_entry(src_dir, src_name, IN_MOVED_FROM, cookie, is_dir);
+    inotify::notify_entry(dst_dir, dst_name, IN_MOVED_TO, cookie, is_dir);
+    inotify::notify_self(node, IN_MOVE_SELF);
+}
+
+/// Pick a name for `node` that does not exist in `dir`
+fn unused_name(dir: &Location, node: &Location) -> Result<String, LinuxError> {
+    let mut n = 0u64;
+    loop {
+        let name = alloc::format!(".rename-exchange-{}-{}", node.inode(), n);
+        match lookup(dir, &name) {
+            Ok(_) => n += 1,
+            Err(LinuxError::ENOENT) => return Ok(name),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Swap two directory entries for RENAME_EXCHANGE by going through a
+/// temporary name in the source directory that is checked to be unused.
+/// A failing step undoes the steps before it, so both entries keep their names.
+fn exchange(
+    src_dir: &Location,
+    src_name: &str,
+    dst_dir: &Location,
+    dst_name: &str,
+) -> Result<(), LinuxError> {
+    let src = lookup(src_dir, src_name)?;
+    let dst = lookup(dst_dir, dst_name)?;
+    if src.inode() == dst.inode() {
+        // Both names refer to the same node; swapping them changes nothing
+        return Ok(());
+    }
+    
+    let tmp_name = unused_name(src_dir, &src)?;
+    src_dir
+        .rename(src_name, src_dir, &tmp_name)
+        .map_err(ax_err_to_linux)?;
+    if let Err(e) = dst_dir.rename(dst_name, src_dir, src_name) {
+        let _ = src_dir.rename(&tmp_name, src_dir, src_name);
+        return Err(ax_err_to_linux(e));
+    }
+    if let Err(e) = src_dir.rename(&tmp_name, dst_dir, dst_name) {
+        let _ = src_dir.rename(src_name, dst_dir, dst_name);
+        let _ = src_dir.rename(&tmp_name, src_dir, src_name);
+        return Err(ax_err_to_linux(e));
+    }
+    
+    // Watchers see two renames, not the temporary name
+    notify_moved(src_dir, src_name, dst_dir, dst_name, &src);
+    notify_moved(dst_dir, dst_name, src_dir, src_name, &dst);
+    Ok(())
+}
+
+/// Syscall: renameat2(2) - Rename a file, optionally without replacing the
+/// target (RENAME_NOREPLACE) or by swapping the two names (RENAME_EXCHANGE)
+/// Returns 0 on success, negative error on failure
+pub fn sys_renameat2(
+    olddirfd: isize,
+    oldpath: usize,
+    newdirfd: isize,
+    newpath: usize,
+    flags: u32,
+) -> isize {
+    if flags & !(RENAME_NOREPLACE | RENAME_EXCHANGE | RENAME_WHITEOUT) != 0
+        || flags & (RENAME_NOREPLACE | RENAME_EXCHANGE) == (RENAME_NOREPLACE | RENAME_EXCHANGE)
+    {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    if flags & RENAME_WHITEOUT != 0 {
+        // Whiteouts only exist on overlay-style filesystems
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    
+    to_isize((|| -> Result<(), LinuxError> {
+        let oldpath = load_path(oldpath)?;
+        let newpath = load_path(newpath)?;
+        let (src_dir, src_name) = parent_at(olddirfd, &oldpath)?;
+        let (dst_dir, dst_name) = parent_at(newdirfd, &newpath)?;
+        for name in [&src_name, &dst_name] {
+            if name == "." || name == ".." {
+                return Err(LinuxError::EBUSY);
+            }
+        }
+        check_same_fs(&src_dir, &dst_dir)?;
+        
+        if flags & RENAME_EXCHANGE != 0 {
+            return exchange(&src_dir, &src_name, &dst_dir, &dst_name);
+        }
+        
+        let src = lookup(&src_dir, &src_name)?;
+        match lookup(&dst_dir, &dst_name) {
+            Ok(_) if flags & RENAME_NOREPLACE != 0 => return Err(LinuxError::EEXIST),
+            Ok(dst) if src.inode() == dst.inode() => return Ok(()),
+            Ok(dst) if src.is_dir() && !dst.is_dir() => return Err(LinuxError::ENOTDIR),
+            Ok(dst) if !src.is_dir() && dst.is_dir() => return Err(LinuxError::EISDIR),
+            _ => {}
+        }
+        src_dir
+            .rename(&src_name, &dst_dir, &dst_name)
+            .map_err(ax_err_to_linux)?;
+        notify_moved(&src_dir, &src_name, &dst_dir, &dst_name, &src);
+        Ok(())
+    })())
+}
+
+/// Syscall: linkat(2) - Create a hard link
+/// AT_SYMLINK_FOLLOW links the symlink target instead of the symlink itself;
+/// AT_EMPTY_PATH with an empty `oldpath` links the file `olddirfd` refers to
+/// Returns 0 on success, negative error on failure
+pub fn sys_linkat(
+    olddirfd: isize,
+    oldpath: usize,
+    newdirfd: isize,
+    newpath: usize,
+    flags: u32,
+) -> isize {
+    if flags & !(AT_SYMLINK_FOLLOW | AT_EMPTY_PATH) != 0 {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    
+    to_isize((|| -> Result<(), LinuxError> {
+        let oldpath = load_user_cstring(oldpath).map_err(ax_err_to_linux)?;
+        let newpath = load_path(newpath)?;
+        
+        let src = if oldpath.is_empty() {
+            if flags & AT_EMPTY_PATH == 0 {
+                return Err(LinuxError::ENOENT);
+            }
+            // AT_FDCWD names the current directory, which cannot be linked
+            if olddirfd == AT_FDCWD {
+                return Err(LinuxError::EPERM);
+            }
+            let file = fd_table::get_file(olddirfd as i32).ok_or(LinuxError::EBADF)?;
+            file.location().ok_or(LinuxError::ENOENT)?.clone()
+        } else {
+            let fs = fs_context_at(olddirfd, &oldpath)?;
+            let res = if flags & AT_SYMLINK_FOLLOW != 0 {
+                fs.resolve(&oldpath)
+            } else {
+                fs.resolve_no_follow(&oldpath)
+            };
+            res.map_err(ax_err_to_linux)?
+        };
+        if src.is_dir() {
+            return Err(LinuxError::EPERM);
+        }
+        
+        let (dir, name) = parent_at(newdirfd, &newpath)?;
+        if name == "." || name == ".." || lookup(&dir, &name).is_ok() {
+            return Err(LinuxError::EEXIST);
+        }
+        check_same_fs(&src, &dir)?;
+        dir.link(&name, &src).map_err(ax_err_to_linux)?;
+        inotify::notify_entry(&dir, &name, IN_CREATE, 0, false);
+        inotify::notify_self(&src, IN_ATTRIB);
+        Ok(())
+    })())
+}
+
+/// Syscall: symlinkat(2) - Create a symbolic link at `linkpath` pointing to `target`
+/// The target is stored verbatim and not resolved
+/// Returns 0 on success, negative error on failure
+pub fn sys_symlinkat(target: usize, newdirfd: isize, linkpath: usize) -> isize {
+    to_isize((|| -> Result<(), LinuxError> {
+        let target = load_path(target)?;
+        let linkpath = load_path(linkpath)?;
+        let (dir, name) = parent_at(newdirfd, &linkpath)?;
+        if name == "." || name == ".." || lookup(&dir, &name).is_ok() {
+            return Err(LinuxError::EEXIST);
+        }
+        
+        let perm = NodePermission::from_bits_truncate(0o777);
+        let link = dir
+            .create(&name, NodeType::Symlink, perm)
+            .map_err(ax_err_to_linux)?;
+        let written = link
+            .entry()
+            .as_file()
+            .and_then(|f| f.write_at(target.as_bytes(), 0));
+        if let Err(e) = written {
+            // Never leave an empty symlink behind
+            let _ = dir.unlink(&name, false);
+            return Err(ax_err_to_linux(e));
+        }
+        inotify::notify_entry(&dir, &name, IN_CREATE, 0, false);
+        Ok(())
+    })())
+}
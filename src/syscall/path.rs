@@ -0,0 +1,46 @@
+/// Path resolution shared by the *at syscalls
+///
+/// Relative paths are looked up from `dirfd` (or the current directory for
+/// `AT_FDCWD`); absolute paths ignore `dirfd` entirely, as on Linux.
+
+use alloc::string::String;
+
+use axerrno::LinuxError;
+use axfs::FsContext;
+use axfs_ng_vfs::Location;
+
+use super::{ax_err_to_linux, fd_table};
+
+pub const AT_FDCWD: isize = -100;
+
+/// Get the global filesystem context
+pub fn get_fs_context() -> Result<FsContext, LinuxError> {
+    axfs::ROOT_FS_CONTEXT
+        .get()
+        .cloned()
+        .ok_or(LinuxError::ENOENT)
+}
+
+/// Get the filesystem context a lookup of `path` relative to `dirfd` starts from
+///
+/// Errors:
+/// - EBADF: `dirfd` is neither AT_FDCWD nor an open fd
+/// - ENOTDIR: `dirfd` does not refer to a directory
+pub fn fs_context_at(dirfd: isize, path: &str) -> Result<FsContext, LinuxError> {
+    let fs = get_fs_context()?;
+    if dirfd == AT_FDCWD || path.starts_with('/') {
+        return Ok(fs);
+    }
+    
+    let file = fd_table::get_file(dirfd as i32).ok_or(LinuxError::EBADF)?;
+    let dir = file.as_dir()?;
+    fs.with_current_dir(dir.clone()).map_err(ax_err_to_linux)
+}
+
+/// Resolve the directory that will contain the last component of `path`,
+/// looked up relative to `dirfd`. Returns that directory and the final name.
+pub fn parent_at(dirfd: isize, path: &str) -> Result<(Location, String), LinuxError> {
+    let fs = fs_context_at(dirfd, path)?;
+    let (dir, name) = fs.resolve_parent(path).map_err(ax_err_to_linux)?;
+    Ok((dir, name.into()))
+}
@@ -0,0 +1,322 @@
+/// Anonymous pipes (pipe2)
+///
+/// Both ends share one bounded ring buffer. Each end is owned by exactly one
+/// open file description, so dropping the description closes that end:
+/// readers then see EOF, writers get EPIPE and SIGPIPE.
+
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::{any::Any, task::Context, time::Duration};
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::{DeviceId, Metadata, NodePermission, NodeType};
+use axpoll::{IoEvents, PollSet};
+use axtask::WaitQueue;
+use linux_raw_sys::general::{O_CLOEXEC, O_NONBLOCK, O_RDONLY, O_WRONLY};
+
+use super::{
+    fd_table::{self, SpinLock, FD_CLOEXEC, FD_NONBLOCK},
+    file::FileLike,
+    linux_err_to_isize, signal,
+};
+
+/// Pipe capacity, the Linux default of 16 pages
+const PIPE_SIZE: usize = 65536;
+
+/// Writes of at most this many bytes are never interleaved with other writes
+const PIPE_BUF: usize = 4096;
+
+/// Bounded byte ring buffer
+struct RingBuffer {
+    data: Vec<u8>,
+    head: usize,    // Index of the oldest byte
+    len: usize,     // Number of bytes stored
+}
+
+impl RingBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            data: vec![0; capacity],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn free(&self) -> usize {
+        self.data.len() - self.len
+    }
+
+    /// Copy out up to `buf.len()` bytes, returning how many were read
+    fn pop(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.len);
+        let cap = self.data.len();
+        let first = n.min(cap - self.head);
+        buf[..first].copy_from_slice(&self.data[self.head..self.head + first]);
+        buf[first..n].copy_from_slice(&self.data[..n - first]);
+        self.head = (self.head + n) % cap;
+        self.len -= n;
+        n
+    }
+
+    /// Copy in as much of `buf` as fits, returning how many were written
+    fn push(&mut self, buf: &[u8]) -> usize {
+        let n = buf.len().min(self.free());
+        let cap = self.data.len();
+        let tail = (self.head + self.len) % cap;
+        let first = n.min(cap - tail);
+        self.data[tail..tail + first].copy_from_slice(&buf[..first]);
+        self.data[..n - first].copy_from_slice(&buf[first..n]);
+        self.len += n;
+        n
+    }
+}
+
+/// Buffer and end states, guarded by one lock
+struct PipeInner {
+    buf: RingBuffer,
+    reader_open: bool,
+    writer_open: bool,
+}
+
+/// State shared by both ends of a pipe
+struct PipeShared {
+    state: SpinLock<PipeInner>,
+    /// Woken when data arrives or the write end closes
+    read_ready: PollSet,
+    /// Woken when room frees up or the read end closes
+    write_ready: PollSet,
+    /// Blocked readers, woken along with `read_ready`
+    read_wq: WaitQueue,
+    /// Blocked writers, woken along with `write_ready`
+    write_wq: WaitQueue,
+}
+
+impl PipeShared {
+    /// Data arrived or the write end closed
+    fn wake_readers(&self) {
+        self.read_ready.wake();
+        self.read_wq.notify_all(false);
+    }
+
+    /// Room freed up or the read end closed
+    fn wake_writers(&self) {
+        self.write_ready.wake();
+        self.write_wq.notify_all(false);
+    }
+}
+
+/// One end of a pipe
+pub struct Pipe {
+    inner: Arc<PipeShared>,
+    readable: bool,
+}
+
+impl Pipe {
+    /// Create a connected (read end, write end) pair
+    pub fn new_pair() -> (Pipe, Pipe) {
+        let inner = Arc::new(PipeShared {
+            state: SpinLock::new(PipeInner {
+                buf: RingBuffer::new(PIPE_SIZE),
+                reader_open: true,
+                writer_open: true,
+            }),
+            read_ready: PollSet::new(),
+            write_ready: PollSet::new(),
+            read_wq: WaitQueue::new(),
+            write_wq: WaitQueue::new(),
+        });
+        let reader = Pipe {
+            inner: inner.clone(),
+            readable: true,
+        };
+        let writer = Pipe {
+            inner,
+            readable: false,
+        };
+        (reader, writer)
+    }
+}
+
+impl FileLike for Pipe {
+    /// Read whatever is buffered, waiting for data unless the description is nonblocking.
+    /// Returns 0 once the buffer is empty and the write end is closed.
+    fn read(&self, buf: &mut [u8], flags: u32) -> Result<usize, LinuxError> {
+        if !self.readable {
+            return Err(LinuxError::EBADF);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            {
+                let mut inner = self.inner.state.lock();
+                if inner.buf.len > 0 {
+                    let n = inner.buf.pop(buf);
+                    drop(inner);
+                    self.inner.wake_writers();
+                    return Ok(n);
+                }
+                if !inner.writer_open {
+                    return Ok(0);
+                }
+            }
+            if flags & FD_NONBLOCK != 0 {
+                return Err(LinuxError::EAGAIN);
+            }
+            self.inner.read_wq.wait_until(|| {
+                let inner = self.inner.state.lock();
+                inner.buf.len > 0 || !inner.writer_open
+            });
+        }
+    }
+
+    /// Write all of `buf`, waiting for room unless the description is nonblocking.
+    /// Writes of up to PIPE_BUF bytes go in as one piece; larger ones may be split.
+    fn write(&self, buf: &[u8], flags: u32) -> Result<usize, LinuxError> {
+        if self.readable {
+            return Err(LinuxError::EBADF);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let atomic = buf.len() <= PIPE_BUF;
+        let mut written = 0;
+        loop {
+            {
+                let mut inner = self.inner.state.lock();
+                if !inner.reader_open {
+                    drop(inner);
+                    signal::raise(signal::SIGPIPE);
+                    // Linux reports what was already written before the reader went away
+                    return if written > 0 {
+                        Ok(written)
+                    } else {
+                        Err(LinuxError::EPIPE)
+                    };
+                }
+                let free = inner.buf.free();
+                if !(atomic && free < buf.len()) && free > 0 {
+                    written += inner.buf.push(&buf[written..]);
+                    drop(inner);
+                    self.inner.wake_readers();
+                    if written == buf.len() {
+                        return Ok(written);
+                    }
+                }
+            }
+            if flags & FD_NONBLOCK != 0 {
+                return if written > 0 {
+                    Ok(written)
+                } else {
+                    Err(LinuxError::EAGAIN)
+                };
+            }
+            // Atomic writes wait until the whole buffer fits
+            let needed = if atomic { buf.len() } else { 1 };
+            self.inner.write_wq.wait_until(|| {
+                let inner = self.inner.state.lock();
+                inner.buf.free() >= needed || !inner.reader_open
+            });
+        }
+    }
+
+    /// Metadata reported by fstat on a pipe fd
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        Ok(Metadata {
+            device: 0,
+            inode: Arc::as_ptr(&self.inner) as u64,
+            nlink: 1,
+            mode: NodePermission::from_bits_truncate(0o600),
+            node_type: NodeType::Fifo,
+            uid: 0,
+            gid: 0,
+            size: 0,
+            block_size: PIPE_BUF as u64,
+            blocks: 0,
+            rdev: DeviceId::new(0, 0),
+            atime: Duration::ZERO,
+            mtime: Duration::ZERO,
+            ctime: Duration::ZERO,
+        })
+    }
+
+    /// The read end is readable with data buffered and hung up once the writer is gone;
+    /// the write end is writable while PIPE_BUF bytes fit and errors once the reader is gone
+    fn poll(&self) -> IoEvents {
+        let inner = self.inner.state.lock();
+        let mut events = IoEvents::empty();
+        if self.readable {
+            if inner.buf.len > 0 {
+                events |= IoEvents::IN;
+            }
+            if !inner.writer_open {
+                events |= IoEvents::HUP;
+            }
+        } else {
+            if inner.buf.free() >= PIPE_BUF {
+                events |= IoEvents::OUT;
+            }
+            if !inner.reader_open {
+                events |= IoEvents::ERR;
+            }
+        }
+        events
+    }
+
+    fn register(&self, context: &mut Context<'_>, _events: IoEvents) {
+        if self.readable {
+            self.inner.read_ready.register(context.waker());
+        } else {
+            self.inner.write_ready.register(context.waker());
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        let mut inner = self.inner.state.lock();
+        if self.readable {
+            inner.reader_open = false;
+            drop(inner);
+            self.inner.wake_writers();
+        } else {
+            inner.writer_open = false;
+            drop(inner);
+            self.inner.wake_readers();
+        }
+    }
+}
+
+/// Syscall: pipe2(2) - Create a pipe
+/// Stores the read end in fds[0] and the write end in fds[1].
+/// Supported flags: O_CLOEXEC, O_NONBLOCK
+/// Returns 0 on success, negative error on failure
+pub fn sys_pipe2(fds: usize, flags: u32) -> isize {
+    if flags & !(O_CLOEXEC | O_NONBLOCK) != 0 {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+
+    let fd_flags = if flags & O_CLOEXEC != 0 { FD_CLOEXEC } else { 0 };
+    let nonblock = flags & O_NONBLOCK;
+    let (reader, writer) = Pipe::new_pair();
+
+    let read_fd = match fd_table::add_file(reader, O_RDONLY | nonblock, fd_flags) {
+        Ok(fd) => fd,
+        Err(e) => return linux_err_to_isize(e),
+    };
+    let write_fd = match fd_table::add_file(writer, O_WRONLY | nonblock, fd_flags) {
+        Ok(fd) => fd,
+        Err(e) => {
+            fd_table::remove_file(read_fd);
+            return linux_err_to_isize(e);
+        }
+    };
+
+    unsafe {
+        (fds as *mut [i32; 2]).write_unaligned([read_fd, write_fd]);
+    }
+    0
+}
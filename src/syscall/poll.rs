@@ -0,0 +1,324 @@
+/// I/O multiplexing: ppoll and pselect6
+///
+/// riscv64 has no poll or select syscalls; libc implements them on top of
+/// ppoll and pselect6. Waiting registers a waker with every polled object
+/// through `FileLike::register` (axpoll) and sleeps on a wait queue until one
+/// fires, the timeout passes, or a short tick elapses. The tick covers objects
+/// without a wakeup source, such as the console.
+
+use alloc::{sync::Arc, task::Wake, vec, vec::Vec};
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Waker},
+    time::Duration,
+};
+
+use axerrno::LinuxError;
+use axpoll::IoEvents;
+use axtask::WaitQueue;
+use linux_raw_sys::general::{__kernel_timespec, pollfd};
+
+use super::{
+    fd_table::{self, OpenFile},
+    linux_err_to_isize, signal,
+};
+
+/// Longest sleep between two scans of the polled objects
+const POLL_TICK: Duration = Duration::from_millis(10);
+
+/// Wakes the polling task when a registered object may have become ready
+struct PollWaiter {
+    woken: AtomicBool,
+    wq: WaitQueue,
+}
+
+impl Wake for PollWaiter {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        self.wq.notify_all(false);
+    }
+}
+
+/// Current monotonic time
+pub fn now() -> Duration {
+    axhal::time::monotonic_time()
+}
+
+/// Scan until `scan` reports something ready or `timeout` passes (None waits forever).
+/// `scan` is handed a context to register wakeups with on the first pass and after
+/// each wakeup, which is when a registration may have been used up; periodic
+/// rescans and zero timeouts get None.
+/// Returns the last result of `scan`, which is 0 on timeout.
+pub fn wait_ready<F>(timeout: Option<Duration>, mut scan: F) -> Result<usize, LinuxError>
+where
+    F: FnMut(Option<&mut Context<'_>>) -> Result<usize, LinuxError>,
+{
+    if timeout == Some(Duration::ZERO) {
+        return scan(None);
+    }
+
+    let deadline = timeout.map(|t| now() + t);
+    let waiter = Arc::new(PollWaiter {
+        woken: AtomicBool::new(false),
+        wq: WaitQueue::new(),
+    });
+    let waker = Waker::from(waiter.clone());
+    let mut register = true;
+    loop {
+        waiter.woken.store(false, Ordering::Release);
+        let mut cx = Context::from_waker(&waker);
+        let n = scan(if register { Some(&mut cx) } else { None })?;
+        if n > 0 {
+            return Ok(n);
+        }
+        let sleep = match deadline {
+            Some(deadline) => {
+                let current = now();
+                if current >= deadline {
+                    return Ok(0);
+                }
+                (deadline - current).min(POLL_TICK)
+            }
+            None => POLL_TICK,
+        };
+        waiter
+            .wq
+            .wait_timeout_until(sleep, || waiter.woken.load(Ordering::Acquire));
+        register = waiter.woken.load(Ordering::Acquire);
+    }
+}
+
+/// Events currently ready on `file`, with the *NORM aliases Linux reports alongside IN and OUT
+pub fn file_events(file: &OpenFile) -> IoEvents {
+    let mut events = file.object.poll();
+    if events.contains(IoEvents::IN) {
+        events |= IoEvents::RDNORM;
+    }
+    if events.contains(IoEvents::OUT) {
+        events |= IoEvents::WRNORM;
+    }
+    events
+}
+
+/// Convert a user timespec, rejecting negative times and out-of-range nanoseconds
+pub fn timespec_to_duration(ts: &__kernel_timespec) -> Result<Duration, LinuxError> {
+    if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+/// Convert a duration to a user timespec
+pub fn duration_to_timespec(d: Duration) -> __kernel_timespec {
+    __kernel_timespec {
+        tv_sec: d.as_secs() as _,
+        tv_nsec: d.subsec_nanos() as _,
+    }
+}
+
+/// Load an optional timeout; NULL means wait forever
+pub fn load_timeout(ptr: usize) -> Result<Option<Duration>, LinuxError> {
+    if ptr == 0 {
+        return Ok(None);
+    }
+    let ts = unsafe { (ptr as *const __kernel_timespec).read_unaligned() };
+    timespec_to_duration(&ts).map(Some)
+}
+
+/// Write back the time left of `timeout` after waiting since `start`, as Linux does
+fn store_remaining(ptr: usize, timeout: Option<Duration>, start: Duration) {
+    if let Some(timeout) = timeout {
+        let left = timeout.saturating_sub(now() - start);
+        unsafe { (ptr as *mut __kernel_timespec).write_unaligned(duration_to_timespec(left)) };
+    }
+}
+
+/// Run `wait` with the blocked signal mask temporarily replaced by `mask`
+pub fn with_sigmask<T>(mask: Option<u64>, wait: impl FnOnce() -> T) -> T {
+    let old = mask.map(signal::set_blocked);
+    let res = wait();
+    if let Some(old) = old {
+        signal::set_blocked(old);
+    }
+    res
+}
+
+/// Syscall: ppoll(2) - Wait for events on a set of fds
+/// Negative fds are skipped; fds that are not open report POLLNVAL.
+/// `sigmask` replaces the blocked signals for the duration of the wait.
+/// Returns the number of fds with events, 0 on timeout, negative error on failure
+pub fn sys_ppoll(fds: usize, nfds: usize, tmo: usize, sigmask: usize, sigsetsize: usize) -> isize {
+    let res = (|| -> Result<usize, LinuxError> {
+        if nfds as u64 > fd_table::nofile_limit().0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let timeout = load_timeout(tmo)?;
+        let mask = signal::load_sigset(sigmask, sigsetsize)?;
+        if nfds > 0 && fds == 0 {
+            return Err(LinuxError::EFAULT);
+        }
+        let pollfds: &mut [pollfd] = if nfds == 0 {
+            &mut []
+        } else {
+            unsafe { core::slice::from_raw_parts_mut(fds as *mut pollfd, nfds) }
+        };
+
+        // Look every fd up once; the files stay alive for the whole call
+        let files: Vec<Option<Arc<OpenFile>>> = pollfds
+            .iter()
+            .map(|p| if p.fd < 0 { None } else { fd_table::get_file(p.fd) })
+            .collect();
+
+        let start = now();
+        let res = with_sigmask(mask, || {
+            wait_ready(timeout, |mut cx| {
+                let mut ready = 0;
+                for (p, file) in pollfds.iter_mut().zip(&files) {
+                    p.revents = 0;
+                    if p.fd < 0 {
+                        continue;
+                    }
+                    // Errors, hangups and invalid fds are reported even if not requested
+                    let wanted = IoEvents::from_bits_truncate(p.events as u16 as u32)
+                        | IoEvents::ERR
+                        | IoEvents::HUP
+                        | IoEvents::NVAL;
+                    let revents = match file {
+                        Some(file) => {
+                            if let Some(cx) = cx.as_deref_mut() {
+                                file.object.register(cx, wanted);
+                            }
+                            file_events(file) & wanted
+                        }
+                        None => IoEvents::NVAL,
+                    };
+                    if !revents.is_empty() {
+                        p.revents = revents.bits() as i16;
+                        ready += 1;
+                    }
+                }
+                Ok(ready)
+            })
+        });
+        store_remaining(tmo, timeout, start);
+        res
+    })();
+
+    match res {
+        Ok(n) => n as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Load a user fd_set covering `words` words (all clear if the pointer is NULL)
+fn load_fd_set(ptr: usize, words: usize) -> Vec<u64> {
+    if ptr == 0 {
+        return vec![0; words];
+    }
+    unsafe { core::slice::from_raw_parts(ptr as *const u64, words) }.to_vec()
+}
+
+/// Store a result fd_set back to user space
+fn store_fd_set(ptr: usize, set: &[u64]) {
+    if ptr != 0 {
+        unsafe { core::slice::from_raw_parts_mut(ptr as *mut u64, set.len()) }.copy_from_slice(set);
+    }
+}
+
+/// Syscall: pselect6(2) - Wait for fds in up to three sets to become ready
+/// `sig` points to { const sigset_t *ss; size_t ss_len; } or is NULL.
+/// On return each set holds only the ready fds.
+/// Returns the total number of bits set, 0 on timeout, negative error on failure
+pub fn sys_pselect6(
+    nfds: i32,
+    readfds: usize,
+    writefds: usize,
+    exceptfds: usize,
+    tmo: usize,
+    sig: usize,
+) -> isize {
+    let res = (|| -> Result<usize, LinuxError> {
+        if nfds < 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        // fds past the open-file limit can never be open
+        let nfds = (nfds as u64).min(fd_table::nofile_limit().0) as usize;
+        let timeout = load_timeout(tmo)?;
+        let mask = if sig == 0 {
+            None
+        } else {
+            let [ss, ss_len] = unsafe { (sig as *const [usize; 2]).read_unaligned() };
+            signal::load_sigset(ss, ss_len)?
+        };
+
+        let words = nfds.div_ceil(64);
+        let sets = [
+            load_fd_set(readfds, words),
+            load_fd_set(writefds, words),
+            load_fd_set(exceptfds, words),
+        ];
+
+        // Every fd named in any set must be open
+        let mut watched = Vec::new();
+        for fd in 0..nfds {
+            let bit = 1u64 << (fd % 64);
+            if sets.iter().any(|set| set[fd / 64] & bit != 0) {
+                let file = fd_table::get_file(fd as i32).ok_or(LinuxError::EBADF)?;
+                watched.push((fd, file));
+            }
+        }
+
+        // Events that make an fd count as ready in the read, write and except sets
+        let classes = [
+            IoEvents::IN | IoEvents::RDNORM | IoEvents::RDBAND | IoEvents::HUP | IoEvents::ERR,
+            IoEvents::OUT | IoEvents::WRNORM | IoEvents::WRBAND | IoEvents::ERR,
+            IoEvents::PRI,
+        ];
+
+        let mut result = [vec![0u64; words], vec![0u64; words], vec![0u64; words]];
+        let start = now();
+        let res = with_sigmask(mask, || {
+            wait_ready(timeout, |mut cx| {
+                let mut ready = 0;
+                for set in result.iter_mut() {
+                    set.fill(0);
+                }
+                for (fd, file) in &watched {
+                    let (w, bit) = (fd / 64, 1u64 << (fd % 64));
+                    if let Some(cx) = cx.as_deref_mut() {
+                        let wanted = sets
+                            .iter()
+                            .zip(classes)
+                            .filter(|(set, _)| set[w] & bit != 0)
+                            .fold(IoEvents::empty(), |acc, (_, class)| acc | class);
+                        file.object.register(cx, wanted);
+                    }
+                    let events = file_events(file);
+                    for ((set, out), class) in sets.iter().zip(result.iter_mut()).zip(classes) {
+                        if set[w] & bit != 0 && events.intersects(class) {
+                            out[w] |= bit;
+                            ready += 1;
+                        }
+                    }
+                }
+                Ok(ready)
+            })
+        });
+        store_remaining(tmo, timeout, start);
+        let ready = res?;
+
+        store_fd_set(readfds, &result[0]);
+        store_fd_set(writefds, &result[1]);
+        store_fd_set(exceptfds, &result[2]);
+        Ok(ready)
+    })();
+
+    match res {
+        Ok(n) => n as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
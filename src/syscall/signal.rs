@@ -0,0 +1,129 @@
+/// Signal mask and delivery
+///
+/// There are no user signal handlers yet, so a raised signal that is not
+/// blocked takes its default action right away. A blocked one stays pending
+/// until it is consumed.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use axerrno::LinuxError;
+use axtask::WaitQueue;
+use linux_raw_sys::general::{SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK};
+
+use super::{linux_err_to_isize, task::exit_process};
+
+/// Kill: can be neither blocked nor caught
+pub const SIGKILL: u32 = 9;
+
+/// Broken pipe: write to a pipe with no readers
+pub const SIGPIPE: u32 = 13;
+
+/// Stop: can be neither blocked nor caught
+pub const SIGSTOP: u32 = 19;
+
+/// Size of the kernel sigset_t user space must pass
+pub const SIGSET_SIZE: usize = 8;
+
+/// Signals blocked by the process
+static BLOCKED: AtomicU64 = AtomicU64::new(0);
+
+/// Signals raised while blocked
+static PENDING: AtomicU64 = AtomicU64::new(0);
+
+/// Tasks waiting for a signal to become pending
+static PENDING_WQ: WaitQueue = WaitQueue::new();
+
+/// Bit of `sig` in a sigset
+pub const fn sig_bit(sig: u32) -> u64 {
+    1 << (sig - 1)
+}
+
+/// Get the blocked signal mask
+pub fn blocked() -> u64 {
+    BLOCKED.load(Ordering::Relaxed)
+}
+
+/// Replace the blocked signal mask, returning the old one.
+/// SIGKILL and SIGSTOP are silently left unblocked, as on Linux.
+/// A pending signal the new mask unblocks is delivered right away.
+pub fn set_blocked(mask: u64) -> u64 {
+    let mask = mask & !(sig_bit(SIGKILL) | sig_bit(SIGSTOP));
+    let old = BLOCKED.swap(mask, Ordering::Relaxed);
+    let unblocked = pending() & !mask;
+    if unblocked != 0 {
+        exit_process(128 + unblocked.trailing_zeros() as i32 + 1);
+    }
+    old
+}
+
+/// Get the signals that are waiting to be consumed
+pub fn pending() -> u64 {
+    PENDING.load(Ordering::Relaxed)
+}
+
+/// Consume the lowest-numbered pending signal in `mask`, if any
+pub fn take_pending(mask: u64) -> Option<u32> {
+    PENDING
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+            let found = pending & mask;
+            (found != 0).then(|| pending & !(found & found.wrapping_neg()))
+        })
+        .ok()
+        .map(|pending| (pending & mask).trailing_zeros() + 1)
+}
+
+/// Sleep until a signal in `mask` is pending
+pub fn wait_pending(mask: u64) {
+    PENDING_WQ.wait_until(|| pending() & mask != 0);
+}
+
+/// Load an optional user sigset_t (NULL means "leave the mask alone")
+pub fn load_sigset(ptr: usize, size: usize) -> Result<Option<u64>, LinuxError> {
+    if ptr == 0 {
+        return Ok(None);
+    }
+    if size != SIGSET_SIZE {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(Some(unsafe { (ptr as *const u64).read_unaligned() }))
+}
+
+/// Raise `sig` for the calling process.
+/// If it is blocked it becomes pending; otherwise its default action
+/// terminates the process and the shell reports status 128 + sig.
+pub fn raise(sig: u32) {
+    if blocked() & sig_bit(sig) != 0 {
+        PENDING.fetch_or(sig_bit(sig), Ordering::Relaxed);
+        PENDING_WQ.notify_all(false);
+        return;
+    }
+    exit_process(128 + sig as i32);
+}
+
+/// Syscall: rt_sigprocmask(2) - Examine and change blocked signals
+/// `how` is SIG_BLOCK, SIG_UNBLOCK or SIG_SETMASK; it is ignored when `set` is NULL.
+/// Returns 0 on success, negative error on failure
+pub fn sys_rt_sigprocmask(how: u32, set: usize, oldset: usize, sigsetsize: usize) -> isize {
+    if sigsetsize != SIGSET_SIZE {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    let old = blocked();
+    let mask = if set == 0 {
+        None
+    } else {
+        let set = unsafe { (set as *const u64).read_unaligned() };
+        match how {
+            SIG_BLOCK => Some(old | set),
+            SIG_UNBLOCK => Some(old & !set),
+            SIG_SETMASK => Some(set),
+            _ => return linux_err_to_isize(LinuxError::EINVAL),
+        }
+    };
+    if oldset != 0 {
+        unsafe { (oldset as *mut u64).write_unaligned(old) };
+    }
+    if let Some(mask) = mask {
+        set_blocked(mask);
+    }
+    0
+}
@@ -0,0 +1,133 @@
+/// Signal fds (signalfd4)
+///
+/// A signalfd consumes pending signals from its mask as `signalfd_siginfo`
+/// records. Signals only stay pending while blocked, so callers block them
+/// with rt_sigprocmask first, as on Linux. A blocking read sleeps until a
+/// signal from its mask is raised; pollers notice it on the next poll tick.
+
+use core::{
+    any::Any,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::Metadata;
+use axpoll::IoEvents;
+use linux_raw_sys::general::{signalfd_siginfo, O_RDWR, SFD_CLOEXEC, SFD_NONBLOCK};
+
+use super::{
+    fd_table::{self, FD_CLOEXEC, FD_NONBLOCK},
+    file::{anon_inode_metadata, FileLike},
+    linux_err_to_isize,
+    signal::{self, sig_bit, SIGKILL, SIGSET_SIZE, SIGSTOP},
+};
+
+const RECORD_SIZE: usize = core::mem::size_of::<signalfd_siginfo>();
+
+/// A signalfd object
+pub struct SignalFd {
+    mask: AtomicU64,
+}
+
+impl SignalFd {
+    fn set_mask(&self, mask: u64) {
+        // SIGKILL and SIGSTOP can never be read from a signalfd
+        let mask = mask & !(sig_bit(SIGKILL) | sig_bit(SIGSTOP));
+        self.mask.store(mask, Ordering::Relaxed);
+    }
+}
+
+impl FileLike for SignalFd {
+    /// Consume as many pending signals from the mask as there are whole records in `buf`
+    fn read(&self, buf: &mut [u8], flags: u32) -> Result<usize, LinuxError> {
+        if buf.len() < RECORD_SIZE {
+            return Err(LinuxError::EINVAL);
+        }
+        loop {
+            let mask = self.mask.load(Ordering::Relaxed);
+            let mut read = 0;
+            for record in buf.chunks_exact_mut(RECORD_SIZE) {
+                let Some(sig) = signal::take_pending(mask) else {
+                    break;
+                };
+                // Every signal here is raised by the process itself: si_code SI_USER
+                let mut info: signalfd_siginfo = unsafe { core::mem::zeroed() };
+                info.ssi_signo = sig;
+                let raw = unsafe {
+                    core::slice::from_raw_parts(
+                        &info as *const signalfd_siginfo as *const u8,
+                        RECORD_SIZE,
+                    )
+                };
+                record.copy_from_slice(raw);
+                read += RECORD_SIZE;
+            }
+            if read > 0 {
+                return Ok(read);
+            }
+            if flags & FD_NONBLOCK != 0 {
+                return Err(LinuxError::EAGAIN);
+            }
+            signal::wait_pending(mask);
+        }
+    }
+
+    fn write(&self, _buf: &[u8], _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EINVAL)
+    }
+
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        Ok(anon_inode_metadata(self as *const Self as u64))
+    }
+
+    /// Readable while a signal from the mask is pending
+    fn poll(&self) -> IoEvents {
+        if signal::pending() & self.mask.load(Ordering::Relaxed) != 0 {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Syscall: signalfd4(2) - Create a signal fd, or change the mask of an existing one
+/// With `fd` == -1 a new signalfd is created; otherwise `fd` must be a signalfd.
+/// Supported flags: SFD_NONBLOCK, SFD_CLOEXEC (ignored when updating)
+/// Returns the fd on success, negative error on failure
+pub fn sys_signalfd4(fd: i32, mask: usize, sizemask: usize, flags: u32) -> isize {
+    if flags & !(SFD_NONBLOCK | SFD_CLOEXEC) != 0 || sizemask != SIGSET_SIZE {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    if mask == 0 {
+        return linux_err_to_isize(LinuxError::EFAULT);
+    }
+    let mask = unsafe { (mask as *const u64).read_unaligned() };
+
+    if fd != -1 {
+        let file = match fd_table::get_file(fd) {
+            Some(f) => f,
+            None => return linux_err_to_isize(LinuxError::EBADF),
+        };
+        return match file.downcast_ref::<SignalFd>() {
+            Some(signalfd) => {
+                signalfd.set_mask(mask);
+                fd as isize
+            }
+            None => linux_err_to_isize(LinuxError::EINVAL),
+        };
+    }
+
+    let signalfd = SignalFd {
+        mask: AtomicU64::new(0),
+    };
+    signalfd.set_mask(mask);
+    let fd_flags = if flags & SFD_CLOEXEC != 0 { FD_CLOEXEC } else { 0 };
+    match fd_table::add_file(signalfd, O_RDWR | (flags & SFD_NONBLOCK), fd_flags) {
+        Ok(fd) => fd as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
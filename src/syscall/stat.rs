@@ -0,0 +1,168 @@
+/// File status syscalls: fstat, newfstatat, statx
+///
+/// All of them report the metadata of the VFS node an fd or path refers to,
+/// so directories, symlinks and device nodes are described correctly.
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::Metadata;
+use linux_raw_sys::general::{
+    stat, statx, statx_timestamp, AT_EMPTY_PATH, AT_NO_AUTOMOUNT, AT_STATX_DONT_SYNC,
+    AT_STATX_FORCE_SYNC, AT_SYMLINK_NOFOLLOW, STATX_BASIC_STATS,
+};
+
+use super::{
+    ax_err_to_linux, fd_table,
+    fs::load_user_cstring,
+    linux_err_to_isize,
+    path::{fs_context_at, get_fs_context, AT_FDCWD},
+};
+
+/// Extract the major number of a Linux `dev_t`
+fn dev_major(dev: u64) -> u32 {
+    (((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff)) as u32
+}
+
+/// Extract the minor number of a Linux `dev_t`
+fn dev_minor(dev: u64) -> u32 {
+    ((dev & 0xff) | ((dev >> 12) & !0xff)) as u32
+}
+
+/// Build `st_mode`: file type bits from the node type plus permission bits
+fn file_mode(meta: &Metadata) -> u32 {
+    ((meta.node_type as u32) << 12) | meta.mode.bits() as u32
+}
+
+fn metadata_to_stat(meta: &Metadata) -> stat {
+    let mut st: stat = unsafe { core::mem::zeroed() };
+    st.st_dev = meta.device as _;
+    st.st_ino = meta.inode as _;
+    st.st_mode = file_mode(meta) as _;
+    st.st_nlink = meta.nlink as _;
+    st.st_uid = meta.uid as _;
+    st.st_gid = meta.gid as _;
+    st.st_rdev = meta.rdev.0 as _;
+    st.st_size = meta.size as _;
+    st.st_blksize = meta.block_size as _;
+    st.st_blocks = meta.blocks as _;
+    st.st_atime = meta.atime.as_secs() as _;
+    st.st_atime_nsec = meta.atime.subsec_nanos() as _;
+    st.st_mtime = meta.mtime.as_secs() as _;
+    st.st_mtime_nsec = meta.mtime.subsec_nanos() as _;
+    st.st_ctime = meta.ctime.as_secs() as _;
+    st.st_ctime_nsec = meta.ctime.subsec_nanos() as _;
+    st
+}
+
+fn metadata_to_statx(meta: &Metadata) -> statx {
+    let ts = |t: core::time::Duration| {
+        let mut ts: statx_timestamp = unsafe { core::mem::zeroed() };
+        ts.tv_sec = t.as_secs() as _;
+        ts.tv_nsec = t.subsec_nanos();
+        ts
+    };
+    
+    let mut stx: statx = unsafe { core::mem::zeroed() };
+    stx.stx_mask = STATX_BASIC_STATS;
+    stx.stx_blksize = meta.block_size as _;
+    stx.stx_nlink = meta.nlink as _;
+    stx.stx_uid = meta.uid;
+    stx.stx_gid = meta.gid;
+    stx.stx_mode = file_mode(meta) as _;
+    stx.stx_ino = meta.inode;
+    stx.stx_size = meta.size;
+    stx.stx_blocks = meta.blocks;
+    stx.stx_atime = ts(meta.atime);
+    stx.stx_ctime = ts(meta.ctime);
+    stx.stx_mtime = ts(meta.mtime);
+    stx.stx_rdev_major = dev_major(meta.rdev.0);
+    stx.stx_rdev_minor = dev_minor(meta.rdev.0);
+    stx.stx_dev_major = dev_major(meta.device);
+    stx.stx_dev_minor = dev_minor(meta.device);
+    stx
+}
+
+/// Get the metadata of the node a *stat syscall refers to.
+/// With AT_EMPTY_PATH and an empty path, that is `dirfd` itself.
+fn stat_target(dirfd: isize, path: usize, flags: u32) -> Result<Metadata, LinuxError> {
+    let path = load_user_cstring(path).map_err(ax_err_to_linux)?;
+    
+    if path.is_empty() {
+        if flags & AT_EMPTY_PATH == 0 {
+            return Err(LinuxError::ENOENT);
+        }
+        if dirfd == AT_FDCWD {
+            return get_fs_context()?.current_dir().metadata().map_err(ax_err_to_linux);
+        }
+        let file = fd_table::get_file(dirfd as i32).ok_or(LinuxError::EBADF)?;
+        return file.metadata();
+    }
+    
+    let fs = fs_context_at(dirfd, &path)?;
+    let res = if flags & AT_SYMLINK_NOFOLLOW != 0 {
+        fs.resolve_no_follow(&path)
+    } else {
+        fs.resolve(&path)
+    };
+    res.and_then(|loc| loc.metadata()).map_err(ax_err_to_linux)
+}
+
+/// Syscall: fstat(2) - Get file status
+/// Returns 0 on success, negative error on failure
+pub fn sys_fstat(fd: i32, statbuf: usize) -> isize {
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    
+    match file.metadata() {
+        Ok(meta) => {
+            unsafe { (statbuf as *mut stat).write(metadata_to_stat(&meta)); }
+            0
+        }
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: newfstatat(2) - Get file status relative to a directory fd
+/// Supports AT_EMPTY_PATH and AT_SYMLINK_NOFOLLOW
+/// Returns 0 on success, negative error on failure
+pub fn sys_newfstatat(dirfd: isize, path: usize, statbuf: usize, flags: u32) -> isize {
+    if flags & !(AT_EMPTY_PATH | AT_SYMLINK_NOFOLLOW | AT_NO_AUTOMOUNT) != 0 {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    
+    let res = stat_target(dirfd, path, flags);
+    
+    match res {
+        Ok(meta) => {
+            unsafe { (statbuf as *mut stat).write(metadata_to_stat(&meta)); }
+            0
+        }
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: statx(2) - Get extended file status
+/// All basic fields are always filled in regardless of `mask`;
+/// `stx_mask` reports STATX_BASIC_STATS
+/// Returns 0 on success, negative error on failure
+pub fn sys_statx(dirfd: isize, path: usize, flags: u32, _mask: u32, statxbuf: usize) -> isize {
+    let valid = AT_EMPTY_PATH
+        | AT_SYMLINK_NOFOLLOW
+        | AT_NO_AUTOMOUNT
+        | AT_STATX_FORCE_SYNC
+        | AT_STATX_DONT_SYNC;
+    if flags & !valid != 0 {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    
+    let res = stat_target(dirfd, path, flags);
+    
+    match res {
+        Ok(meta) => {
+            unsafe { (statxbuf as *mut statx).write(metadata_to_statx(&meta)); }
+            0
+        }
+        Err(e) => linux_err_to_isize(e),
+    }
+}
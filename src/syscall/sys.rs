@@ -1,3 +1,17 @@
+use alloc::string::{String, ToString};
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::NodeType;
+
+use super::{
+    ax_err_to_linux,
+    fd_table::{self, FD_NONBLOCK},
+    fs::load_user_cstring,
+    linux_err_to_isize,
+    path::fs_context_at,
+    task,
+};
+
 /// Resource limits constants
 const RLIM_NLIMITS: usize = 16;
 
@@ -8,10 +22,14 @@ pub struct RlimitV {
     pub hard: u64,
 }
 
+/// RLIMIT_NOFILE resource number
+const RLIMIT_NOFILE: u32 = 7;
+
 /// Syscall: prlimit64(2) - Get/set resource limits
-/// In single-process mode, returns default limits
+/// In single-process mode, returns default limits.
+/// RLIMIT_NOFILE is real: it is kept and enforced by the fd table.
 /// Supports: pid (must be 0 or current), resource, new_limit, old_limit
-pub fn sys_prlimit64(pid: i32, resource: u32, _new_limit: usize, old_limit: usize) -> isize {
+pub fn sys_prlimit64(pid: i32, resource: u32, new_limit: usize, old_limit: usize) -> isize {
     // Only support pid 0 (current process)
     if pid != 0 {
         return -22; // EINVAL
@@ -28,12 +46,23 @@ pub fn sys_prlimit64(pid: i32, resource: u32, _new_limit: usize, old_limit: usiz
         2 => RlimitV { soft: 8388608, hard: u64::MAX }, // RLIMIT_DATA (8MB soft, unlimited hard)
         3 => RlimitV { soft: 8388608, hard: u64::MAX }, // RLIMIT_STACK (8MB)
         4 => RlimitV { soft: 1048576, hard: u64::MAX }, // RLIMIT_CORE (1MB)
-        7 => RlimitV { soft: 1024, hard: 1048576 },     // RLIMIT_NOFILE (1024/1M)
+        RLIMIT_NOFILE => {
+            let (soft, hard) = fd_table::nofile_limit();
+            RlimitV { soft, hard }
+        }
         10 => RlimitV { soft: 8388608, hard: 8388608 }, // RLIMIT_MEMLOCK (8MB)
         _ => RlimitV { soft: u64::MAX, hard: u64::MAX }, // Default: unlimited
     };
     
-    // Write old limit if pointer provided
+    // Only RLIMIT_NOFILE can be changed; other new limits are accepted and ignored
+    if new_limit != 0 && resource == RLIMIT_NOFILE {
+        let new = unsafe { &*(new_limit as *const RlimitV) };
+        if let Err(e) = fd_table::set_nofile_limit(new.soft, new.hard) {
+            return linux_err_to_isize(e);
+        }
+    }
+    
+    // Write old limit (the value before any change) if pointer provided
     if old_limit != 0 {
         unsafe {
             let ptr = old_limit as *mut RlimitV;
@@ -42,35 +71,63 @@ pub fn sys_prlimit64(pid: i32, resource: u32, _new_limit: usize, old_limit: usiz
         }
     }
     
-    // Ignore setting new limits for now (return success)
-    // In production, would actually change the limits
-    
     0
 }
 
 /// Syscall: readlinkat(2) - Read value of a symbolic link
-/// For now, returns EINVAL as we don't have proper symlink support
-/// Could return self-exe path for /proc/self/exe
-pub fn sys_readlinkat(_dirfd: i32, path: usize, buf: usize, bufsiz: usize) -> isize {
-    // Load path from user space
-    let path_str = match super::fs::load_user_cstring(path) {
-        Ok(s) => s,
-        Err(_) => return -14, // EFAULT
-    };
+/// `/proc/self/exe` reports the path of the running executable, and
+/// `/proc/self/fd/N` what fd N refers to: the path of its VFS node, or the
+/// display name of objects without one (memfds).
+/// The result is truncated to `bufsiz` and is not NUL-terminated.
+/// Returns number of bytes placed in `buf`, negative error on failure
+pub fn sys_readlinkat(dirfd: i32, path: usize, buf: usize, bufsiz: usize) -> isize {
+    if bufsiz == 0 || bufsiz > isize::MAX as usize {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
     
-    // Special case: /proc/self/exe
-    if path_str == "/proc/self/exe" || path_str.ends_with("/proc/self/exe") {
-        let exe = b"./test";
-        let len = exe.len().min(bufsiz);
+    let res = (|| -> Result<String, LinuxError> {
+        let path_str = load_user_cstring(path).map_err(ax_err_to_linux)?;
         
-        unsafe {
-            core::ptr::copy_nonoverlapping(exe.as_ptr(), buf as *mut u8, len);
+        if path_str == "/proc/self/exe" {
+            // Unknown until the loader records it with task::set_exe_path
+            return task::exe_path().ok_or(LinuxError::ENOENT);
+        }
+        if let Some(fd) = path_str.strip_prefix("/proc/self/fd/") {
+            let fd = fd.parse::<i32>().map_err(|_| LinuxError::ENOENT)?;
+            let file = fd_table::get_file(fd).ok_or(LinuxError::ENOENT)?;
+            if let Some(name) = file.object.display_name() {
+                return Ok(name);
+            }
+            let loc = file.location().ok_or(LinuxError::ENOENT)?;
+            return Ok(loc.absolute_path().map_err(ax_err_to_linux)?.to_string());
+        }
+        
+        // An empty path reads the symlink dirfd itself refers to (O_PATH | O_NOFOLLOW)
+        let loc = if path_str.is_empty() {
+            let file = fd_table::get_file(dirfd).ok_or(LinuxError::EBADF)?;
+            file.location().ok_or(LinuxError::ENOENT)?.clone()
+        } else {
+            fs_context_at(dirfd as isize, &path_str)?
+                .resolve_no_follow(&path_str)
+                .map_err(ax_err_to_linux)?
+        };
+        
+        if loc.node_type() != NodeType::Symlink {
+            return Err(LinuxError::EINVAL);
         }
-        return len as isize;
+        loc.read_link().map_err(ax_err_to_linux)
+    })();
+    
+    match res {
+        Ok(target) => {
+            let len = target.len().min(bufsiz);
+            unsafe {
+                core::ptr::copy_nonoverlapping(target.as_ptr(), buf as *mut u8, len);
+            }
+            len as isize
+        }
+        Err(e) => linux_err_to_isize(e),
     }
-    
-    // For other paths, return "not a symbolic link" error
-    -22 // EINVAL
 }
 
 /// Syscall: getrandom(2) - Obtain random bytes
@@ -137,105 +194,37 @@ pub fn sys_mprotect(addr: usize, len: usize, prot: i32) -> isize {
 }
 
 /// ioctl command constants
-const FIONBIO: u32 = 0x5421;      // Set blocking mode (FIFO non-blocking)
-const TCGETS: u32 = 0x5401;       // Get terminal attributes
-const TCSETS: u32 = 0x5402;       // Set terminal attributes
-const TIOCGWINSZ: u32 = 0x5413;   // Get window size
-const TIOCSWINSZ: u32 = 0x5414;   // Set window size
+const FIONBIO: u32 = 0x5421;      // Set/clear non-blocking I/O
 
-/// Terminal attributes structure (simplified)
-#[repr(C)]
-pub struct Termios {
-    pub c_iflag: u32,
-    pub c_oflag: u32,
-    pub c_cflag: u32,
-    pub c_lflag: u32,
-    pub c_line: u8,
-    pub c_cc: [u8; 32],
-}
-
-/// Window size structure
-#[repr(C)]
-pub struct Winsize {
-    pub ws_row: u16,
-    pub ws_col: u16,
-    pub ws_xpixel: u16,
-    pub ws_ypixel: u16,
+/// Load the `int` argument at `arg`
+fn load_int(arg: usize) -> Result<i32, LinuxError> {
+    if arg == 0 {
+        return Err(LinuxError::EFAULT);
+    }
+    Ok(unsafe { (arg as *const i32).read_unaligned() })
 }
 
 /// Syscall: ioctl(2) - Device-specific input/output control
+/// FIONBIO works on every fd; everything else is handled by the console
+/// (the only TTY we have) and is ENOTTY for other files
 pub fn sys_ioctl(fd: i32, cmd: u32, arg: usize) -> isize {
-    // For stdin/stdout/stderr (fd 0,1,2), handle common ioctl commands
-    match cmd {
-        FIONBIO => {
-            // Set non-blocking mode
-            // arg is pointer to int (0 = blocking, 1 = non-blocking)
-            // For now, just return success
-            0
-        }
-        TCGETS => {
-            // Get terminal attributes
-            if fd == 0 || fd == 1 || fd == 2 {
-                // For TTY, return default termios
-                let termios = Termios {
-                    c_iflag: 0x0300,
-                    c_oflag: 0x0005,
-                    c_cflag: 0xbf08,
-                    c_lflag: 0x8a3b,
-                    c_line: 0,
-                    c_cc: [3, 28, 127, 21, 4, 1, 0, 0, 17, 19, 26, 0, 18, 15, 23, 22, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-                };
-                
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        &termios as *const _ as *const u8,
-                        arg as *mut u8,
-                        core::mem::size_of::<Termios>(),
-                    );
-                }
-                0
-            } else {
-                -25 // ENOTTY
-            }
-        }
-        TCSETS => {
-            // Set terminal attributes - just return success
-            0
-        }
-        TIOCGWINSZ => {
-            // Get window size
-            if fd == 0 || fd == 1 || fd == 2 {
-                let winsize = Winsize {
-                    ws_row: 24,
-                    ws_col: 80,
-                    ws_xpixel: 0,
-                    ws_ypixel: 0,
-                };
-                
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        &winsize as *const _ as *const u8,
-                        arg as *mut u8,
-                        core::mem::size_of::<Winsize>(),
-                    );
-                }
-                0
-            } else {
-                -25 // ENOTTY
-            }
-        }
-        TIOCSWINSZ => {
-            // Set window size - just return success
-            0
-        }
-        _ => {
-            // Unknown command - return EINVAL for most cases
-            // or ENOTTY for terminal-related commands
-            if fd == 0 || fd == 1 || fd == 2 {
-                -25 // ENOTTY
-            } else {
-                -25 // ENOTTY
-            }
-        }
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    
+    if cmd == FIONBIO {
+        // arg is pointer to int (0 = blocking, nonzero = non-blocking)
+        let nonblocking = match load_int(arg) {
+            Ok(value) => value != 0,
+            Err(e) => return linux_err_to_isize(e),
+        };
+        file.update_status_flags(FD_NONBLOCK, if nonblocking { FD_NONBLOCK } else { 0 });
+        return 0;
+    }
+    
+    match file.object.ioctl(cmd, arg) {
+        Ok(ret) => ret,
+        Err(e) => linux_err_to_isize(e),
     }
 }
@@ -3,22 +3,66 @@
 pub enum Sysno {
     Ioctl = 29,
     Fcntl = 25,
+    Flock = 32,
+    Eventfd2 = 19,
+    EpollCreate1 = 20,
+    EpollCtl = 21,
+    EpollPwait = 22,
+    Dup = 23,
+    Dup3 = 24,
+    InotifyInit1 = 26,
+    InotifyAddWatch = 27,
+    InotifyRmWatch = 28,
     Brk = 12,
+    Mkdirat = 34,
+    Unlinkat = 35,
+    Symlinkat = 36,
+    Linkat = 37,
+    Renameat2 = 276,
     Close = 57,
+    Pipe2 = 59,
+    Truncate = 45,
+    Ftruncate = 46,
+    Fallocate = 47,
     OpenAt = 56,
+    Getdents64 = 61,
     Lseek = 62,
     Read = 63,
     Write = 64,
+    Readv = 65,
+    Writev = 66,
+    Pread64 = 67,
+    Pwrite64 = 68,
+    Preadv = 69,
+    Pwritev = 70,
+    Preadv2 = 286,
+    Pwritev2 = 287,
     Fstat = 80,
+    Sync = 81,
+    Fsync = 82,
+    Fdatasync = 83,
+    TimerfdCreate = 85,
+    TimerfdSettime = 86,
+    TimerfdGettime = 87,
+    Syncfs = 267,
     Exit = 93,
     ExitGroup = 94,
     SetRobustList = 99,
+    RtSigprocmask = 135,
     SetTidAddress = 218,
     Mmap = 222,
     Mprotect = 226,
     Prlimit64 = 261,
-    Readlinkat = 79,
+    Pselect6 = 72,
+    Ppoll = 73,
+    Signalfd4 = 74,
+    Readlinkat = 78,
+    Newfstatat = 79,
+    Statx = 291,
     Getrandom = 278,
+    MemfdCreate = 279,
+    CloseRange = 436,
+    EpollPwait2 = 441,
 }
 
 impl Sysno {
@@ -26,22 +70,66 @@ impl Sysno {
         match nr {
             29 => Some(Sysno::Ioctl),
             25 => Some(Sysno::Fcntl),
+            32 => Some(Sysno::Flock),
+            19 => Some(Sysno::Eventfd2),
+            20 => Some(Sysno::EpollCreate1),
+            21 => Some(Sysno::EpollCtl),
+            22 => Some(Sysno::EpollPwait),
+            23 => Some(Sysno::Dup),
+            24 => Some(Sysno::Dup3),
+            26 => Some(Sysno::InotifyInit1),
+            27 => Some(Sysno::InotifyAddWatch),
+            28 => Some(Sysno::InotifyRmWatch),
             12 => Some(Sysno::Brk),
+            34 => Some(Sysno::Mkdirat),
+            35 => Some(Sysno::Unlinkat),
+            36 => Some(Sysno::Symlinkat),
+            37 => Some(Sysno::Linkat),
+            276 => Some(Sysno::Renameat2),
             57 => Some(Sysno::Close),
+            59 => Some(Sysno::Pipe2),
+            45 => Some(Sysno::Truncate),
+            46 => Some(Sysno::Ftruncate),
+            47 => Some(Sysno::Fallocate),
             56 => Some(Sysno::OpenAt),
+            61 => Some(Sysno::Getdents64),
             62 => Some(Sysno::Lseek),
             63 => Some(Sysno::Read),
             64 => Some(Sysno::Write),
+            65 => Some(Sysno::Readv),
+            66 => Some(Sysno::Writev),
+            67 => Some(Sysno::Pread64),
+            68 => Some(Sysno::Pwrite64),
+            69 => Some(Sysno::Preadv),
+            70 => Some(Sysno::Pwritev),
+            286 => Some(Sysno::Preadv2),
+            287 => Some(Sysno::Pwritev2),
             80 => Some(Sysno::Fstat),
+            81 => Some(Sysno::Sync),
+            82 => Some(Sysno::Fsync),
+            83 => Some(Sysno::Fdatasync),
+            85 => Some(Sysno::TimerfdCreate),
+            86 => Some(Sysno::TimerfdSettime),
+            87 => Some(Sysno::TimerfdGettime),
+            267 => Some(Sysno::Syncfs),
             93 => Some(Sysno::Exit),
             94 => Some(Sysno::ExitGroup),
             99 => Some(Sysno::SetRobustList),
+            135 => Some(Sysno::RtSigprocmask),
             218 => Some(Sysno::SetTidAddress),
             222 => Some(Sysno::Mmap),
             226 => Some(Sysno::Mprotect),
             261 => Some(Sysno::Prlimit64),
-            79 => Some(Sysno::Readlinkat),
+            72 => Some(Sysno::Pselect6),
+            73 => Some(Sysno::Ppoll),
+            74 => Some(Sysno::Signalfd4),
+            78 => Some(Sysno::Readlinkat),
+            79 => Some(Sysno::Newfstatat),
+            291 => Some(Sysno::Statx),
             278 => Some(Sysno::Getrandom),
+            279 => Some(Sysno::MemfdCreate),
+            436 => Some(Sysno::CloseRange),
+            441 => Some(Sysno::EpollPwait2),
             _ => None,
         }
     }
@@ -1,16 +1,41 @@
+use alloc::string::String;
+
 use axtask::exit as ax_exit;
 
+use super::{fd_table::SpinLock, lock};
+
+/// Absolute path of the running executable, as reported by /proc/self/exe
+static EXE_PATH: SpinLock<Option<String>> = SpinLock::new(None);
+
+/// Record the path of the executable being started.
+/// The kernel's exec/loader entry point must call this before entering user space.
+pub fn set_exe_path(path: &str) {
+    *EXE_PATH.lock() = Some(String::from(path));
+}
+
+/// Get the path of the running executable, if the loader recorded one
+pub fn exe_path() -> Option<String> {
+    EXE_PATH.lock().clone()
+}
+
+/// End the process with `code`, dropping what it holds beyond its own memory.
+/// There is a single task, so this is the end of every exit path.
+pub fn exit_process(code: i32) -> ! {
+    lock::release_all();
+    ax_exit(code);
+}
+
 /// Syscall: exit(2) - Terminate the current task
 /// This function never returns
 pub fn sys_exit(code: i32) -> isize {
-    ax_exit(code as i32);
+    exit_process(code);
 }
 
 /// Syscall: exit_group(2) - Terminate the entire process group
 /// In single-process mode, this is equivalent to exit()
 /// This function never returns
 pub fn sys_exit_group(code: i32) -> isize {
-    ax_exit(code as i32);
+    exit_process(code);
 }
 
 
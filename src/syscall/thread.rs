@@ -17,6 +17,14 @@ pub fn sys_set_tid_address(tidp: usize) -> isize {
     }
 }
 
+/// ID of the process; it is the only one, so it never changes
+const PID: usize = 1;
+
+/// Get the process ID (for internal use)
+pub fn get_pid() -> usize {
+    PID
+}
+
 /// Get current thread ID (for internal use)
 #[allow(dead_code)]
 pub fn get_tid() -> usize {
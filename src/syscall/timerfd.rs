@@ -0,0 +1,347 @@
+/// Timer fds: timerfd_create, timerfd_settime, timerfd_gettime
+///
+/// Expirations are counted against the timer's clock whenever the timer is
+/// looked at, so read() and poll always see the exact overrun count. Each
+/// timer has one kernel task that sleeps until the next expiration, or until
+/// settime changes it, and wakes anyone polling the timer.
+
+use alloc::sync::{Arc, Weak};
+use core::{
+    any::Any,
+    sync::atomic::{AtomicBool, Ordering},
+    task::Context,
+    time::Duration,
+};
+
+use axerrno::LinuxError;
+use axfs_ng_vfs::Metadata;
+use axpoll::{IoEvents, PollSet};
+use axtask::WaitQueue;
+use linux_raw_sys::general::{
+    __kernel_itimerspec, CLOCK_BOOTTIME, CLOCK_MONOTONIC, CLOCK_REALTIME, O_RDWR, TFD_CLOEXEC,
+    TFD_NONBLOCK, TFD_TIMER_ABSTIME, TFD_TIMER_CANCEL_ON_SET,
+};
+
+use super::{
+    fd_table::{self, SpinLock, FD_CLOEXEC, FD_NONBLOCK},
+    file::{anon_inode_metadata, FileLike},
+    linux_err_to_isize,
+    poll::{duration_to_timespec, timespec_to_duration},
+};
+
+/// Longest a blocking read sleeps before rechecking, in case the timer was reset meanwhile
+const RECHECK: Duration = Duration::from_millis(10);
+
+/// Clock a timer counts against
+#[derive(Clone, Copy)]
+enum Clock {
+    Realtime,
+    Monotonic,
+}
+
+impl Clock {
+    fn now(self) -> Duration {
+        match self {
+            Clock::Realtime => axhal::time::wall_time(),
+            Clock::Monotonic => axhal::time::monotonic_time(),
+        }
+    }
+
+    /// Monotonic time at which this clock will read `t`
+    fn to_monotonic(self, t: Duration) -> Duration {
+        match self {
+            Clock::Realtime => {
+                let offset = axhal::time::wall_time().saturating_sub(axhal::time::monotonic_time());
+                t.saturating_sub(offset)
+            }
+            Clock::Monotonic => t,
+        }
+    }
+}
+
+struct TimerState {
+    /// Next expiration in the timer's clock, None while disarmed
+    next: Option<Duration>,
+    /// Period of an interval timer, zero for a one-shot timer
+    interval: Duration,
+    /// Expirations not read yet
+    ticks: u64,
+}
+
+impl TimerState {
+    /// Count the expirations up to `now` and move `next` past it
+    fn advance(&mut self, now: Duration) {
+        let Some(next) = self.next else {
+            return;
+        };
+        if now < next {
+            return;
+        }
+        if self.interval.is_zero() {
+            self.ticks = self.ticks.saturating_add(1);
+            self.next = None;
+            return;
+        }
+        let periods = (now - next).as_nanos() / self.interval.as_nanos() + 1;
+        self.ticks = self.ticks.saturating_add(periods as u64);
+        let nanos = next.as_nanos() + self.interval.as_nanos() * periods;
+        self.next = Some(Duration::new(
+            (nanos / 1_000_000_000) as u64,
+            (nanos % 1_000_000_000) as u32,
+        ));
+    }
+}
+
+/// Wakes a timer's notifier task early: on settime, and when the timer is closed
+struct Kick {
+    kicked: AtomicBool,
+    wq: WaitQueue,
+}
+
+impl Kick {
+    fn kick(&self) {
+        self.kicked.store(true, Ordering::Release);
+        self.wq.notify_all(false);
+    }
+}
+
+struct TimerShared {
+    clock: Clock,
+    state: SpinLock<TimerState>,
+    /// Woken on every expiration and every settime
+    ready: PollSet,
+    kick: Arc<Kick>,
+}
+
+impl Drop for TimerShared {
+    /// The timer is gone: let its notifier task see that and exit
+    fn drop(&mut self) {
+        self.kick.kick();
+    }
+}
+
+/// A timerfd object
+pub struct TimerFd {
+    shared: Arc<TimerShared>,
+}
+
+impl TimerFd {
+    fn new(clock: Clock) -> Self {
+        let kick = Arc::new(Kick {
+            kicked: AtomicBool::new(false),
+            wq: WaitQueue::new(),
+        });
+        let shared = Arc::new(TimerShared {
+            clock,
+            state: SpinLock::new(TimerState {
+                next: None,
+                interval: Duration::ZERO,
+                ticks: 0,
+            }),
+            ready: PollSet::new(),
+            kick: kick.clone(),
+        });
+        spawn_notifier(Arc::downgrade(&shared), kick);
+        Self { shared }
+    }
+
+    /// Current setting as timerfd_gettime reports it: time to the next expiration and period
+    fn get(&self) -> __kernel_itimerspec {
+        let now = self.shared.clock.now();
+        let mut state = self.shared.state.lock();
+        state.advance(now);
+        let left = state.next.map_or(Duration::ZERO, |next| next - now);
+        __kernel_itimerspec {
+            it_interval: duration_to_timespec(state.interval),
+            it_value: duration_to_timespec(left),
+        }
+    }
+
+    /// Arm (or, with a zero `value`, disarm) the timer and reset the overrun count
+    fn set(&self, value: Duration, interval: Duration, absolute: bool) {
+        let now = self.shared.clock.now();
+        let mut state = self.shared.state.lock();
+        state.ticks = 0;
+        state.interval = interval;
+        state.next = if value.is_zero() {
+            None
+        } else if absolute {
+            Some(value)
+        } else {
+            Some(now + value)
+        };
+        drop(state);
+
+        // The notifier picks up the new setting
+        self.shared.kick.kick();
+        self.shared.ready.wake();
+    }
+}
+
+/// Start the task that sleeps until each expiration of `timer` and wakes pollers.
+/// A settime kicks it to re-read the setting. It holds only a weak reference
+/// to the timer, so closing the timer ends the task.
+fn spawn_notifier(timer: Weak<TimerShared>, kick: Arc<Kick>) {
+    axtask::spawn(move || loop {
+        let deadline = {
+            let Some(shared) = timer.upgrade() else {
+                return;
+            };
+            // Cleared before reading the setting, so a settime after this is never missed
+            kick.kicked.store(false, Ordering::Release);
+            let next = shared.state.lock().next;
+            next.map(|next| shared.clock.to_monotonic(next))
+        };
+        let kicked = || kick.kicked.load(Ordering::Acquire);
+        match deadline {
+            Some(deadline) => {
+                let now = axhal::time::monotonic_time();
+                if deadline > now {
+                    kick.wq.wait_timeout_until(deadline - now, kicked);
+                }
+            }
+            None => kick.wq.wait_until(kicked),
+        }
+
+        let Some(shared) = timer.upgrade() else {
+            return;
+        };
+        let mut state = shared.state.lock();
+        state.advance(shared.clock.now());
+        let expired = state.ticks > 0;
+        drop(state);
+        if expired {
+            shared.ready.wake();
+        }
+    });
+}
+
+impl FileLike for TimerFd {
+    /// Take the number of expirations since the last read as a native-endian u64
+    fn read(&self, buf: &mut [u8], flags: u32) -> Result<usize, LinuxError> {
+        if buf.len() < 8 {
+            return Err(LinuxError::EINVAL);
+        }
+        loop {
+            let next = {
+                let mut state = self.shared.state.lock();
+                state.advance(self.shared.clock.now());
+                if state.ticks > 0 {
+                    let ticks = core::mem::take(&mut state.ticks);
+                    buf[..8].copy_from_slice(&ticks.to_ne_bytes());
+                    return Ok(8);
+                }
+                state.next
+            };
+            if flags & FD_NONBLOCK != 0 {
+                return Err(LinuxError::EAGAIN);
+            }
+            // A disarmed timer may be armed by settime at any point
+            let wait = match next {
+                Some(next) => next.saturating_sub(self.shared.clock.now()).min(RECHECK),
+                None => RECHECK,
+            };
+            axtask::sleep(wait);
+        }
+    }
+
+    fn write(&self, _buf: &[u8], _flags: u32) -> Result<usize, LinuxError> {
+        Err(LinuxError::EINVAL)
+    }
+
+    fn stat(&self) -> Result<Metadata, LinuxError> {
+        Ok(anon_inode_metadata(Arc::as_ptr(&self.shared) as u64))
+    }
+
+    /// Readable once the timer has expired since the last read
+    fn poll(&self) -> IoEvents {
+        let mut state = self.shared.state.lock();
+        state.advance(self.shared.clock.now());
+        if state.ticks > 0 {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+
+    fn register(&self, context: &mut Context<'_>, _events: IoEvents) {
+        self.shared.ready.register(context.waker());
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Syscall: timerfd_create(2) - Create a timer fd
+/// Supported clocks: CLOCK_REALTIME, CLOCK_MONOTONIC and CLOCK_BOOTTIME (which
+/// equals CLOCK_MONOTONIC here, as the system never suspends)
+/// Supported flags: TFD_NONBLOCK, TFD_CLOEXEC
+/// Returns the new fd on success, negative error on failure
+pub fn sys_timerfd_create(clockid: u32, flags: u32) -> isize {
+    let clock = match clockid {
+        CLOCK_REALTIME => Clock::Realtime,
+        CLOCK_MONOTONIC | CLOCK_BOOTTIME => Clock::Monotonic,
+        _ => return linux_err_to_isize(LinuxError::EINVAL),
+    };
+    if flags & !(TFD_NONBLOCK | TFD_CLOEXEC) != 0 {
+        return linux_err_to_isize(LinuxError::EINVAL);
+    }
+    let fd_flags = if flags & TFD_CLOEXEC != 0 { FD_CLOEXEC } else { 0 };
+    let timer = TimerFd::new(clock);
+    match fd_table::add_file(timer, O_RDWR | (flags & TFD_NONBLOCK), fd_flags) {
+        Ok(fd) => fd as isize,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: timerfd_settime(2) - Arm or disarm a timer fd
+/// With TFD_TIMER_ABSTIME `it_value` is an absolute time on the timer's clock.
+/// TFD_TIMER_CANCEL_ON_SET is only valid together with TFD_TIMER_ABSTIME; the
+/// realtime clock is never set here, so reads are never canceled.
+/// Returns 0 on success, negative error on failure
+pub fn sys_timerfd_settime(fd: i32, flags: u32, new_value: usize, old_value: usize) -> isize {
+    let res = (|| -> Result<(), LinuxError> {
+        if flags & !(TFD_TIMER_ABSTIME | TFD_TIMER_CANCEL_ON_SET) != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        if flags & TFD_TIMER_CANCEL_ON_SET != 0 && flags & TFD_TIMER_ABSTIME == 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let file = fd_table::get_file(fd).ok_or(LinuxError::EBADF)?;
+        let timer = file.downcast_ref::<TimerFd>().ok_or(LinuxError::EINVAL)?;
+        if new_value == 0 {
+            return Err(LinuxError::EFAULT);
+        }
+        let spec = unsafe { (new_value as *const __kernel_itimerspec).read_unaligned() };
+        let value = timespec_to_duration(&spec.it_value)?;
+        let interval = timespec_to_duration(&spec.it_interval)?;
+
+        let old = timer.get();
+        timer.set(value, interval, flags & TFD_TIMER_ABSTIME != 0);
+        if old_value != 0 {
+            unsafe { (old_value as *mut __kernel_itimerspec).write_unaligned(old) };
+        }
+        Ok(())
+    })();
+
+    match res {
+        Ok(()) => 0,
+        Err(e) => linux_err_to_isize(e),
+    }
+}
+
+/// Syscall: timerfd_gettime(2) - Get the time to the next expiration and the interval
+/// Returns 0 on success, negative error on failure
+pub fn sys_timerfd_gettime(fd: i32, curr_value: usize) -> isize {
+    let file = match fd_table::get_file(fd) {
+        Some(f) => f,
+        None => return linux_err_to_isize(LinuxError::EBADF),
+    };
+    let timer = match file.downcast_ref::<TimerFd>() {
+        Some(t) => t,
+        None => return linux_err_to_isize(LinuxError::EINVAL),
+    };
+    unsafe { (curr_value as *mut __kernel_itimerspec).write_unaligned(timer.get()) };
+    0
+}